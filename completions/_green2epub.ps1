@@ -20,24 +20,221 @@ Register-ArgumentCompleter -Native -CommandName 'green2epub' -ScriptBlock {
 
     $completions = @(switch ($command) {
         'green2epub' {
+            [CompletionResult]::new('-c', 'c', [CompletionResultType]::ParameterName, 'Cover image to use')
+            [CompletionResult]::new('--cover', 'cover', [CompletionResultType]::ParameterName, 'Cover image to use')
+            [CompletionResult]::new('--cover-layout', 'cover-layout', [CompletionResultType]::ParameterName, 'Layout of the cover generated for books without one [default: centered]')
+            [CompletionResult]::new('--cover-colors', 'cover-colors', [CompletionResultType]::ParameterName, 'Colors of the cover generated for books without one [default: greentext]')
+            [CompletionResult]::new('--cover-format', 'cover-format', [CompletionResultType]::ParameterName, 'Format the cover image is converted to [default: jpeg]')
+            [CompletionResult]::new('--cover-max-size', 'cover-max-size', [CompletionResultType]::ParameterName, 'Largest size of the cover image as `WIDTHxHEIGHT`, larger images are scaled down to fit keeping their aspect ratio [default: 1600x2400]')
+            [CompletionResult]::new('--cover-quality', 'cover-quality', [CompletionResultType]::ParameterName, 'Quality of the cover image when converted to JPEG, from 1 to 100 [default: 85]')
+            [CompletionResult]::new('-s', 's', [CompletionResultType]::ParameterName, 'Greentext subjects/tags')
+            [CompletionResult]::new('--subject', 'subject', [CompletionResultType]::ParameterName, 'Greentext subjects/tags')
+            [CompletionResult]::new('--tag', 'tag', [CompletionResultType]::ParameterName, 'Greentext subjects/tags')
+            [CompletionResult]::new('--author-file-as', 'author-file-as', [CompletionResultType]::ParameterName, 'Name an author is sorted by, like `Doe, John`')
+            [CompletionResult]::new('--editor', 'editor', [CompletionResultType]::ParameterName, 'Name of an editor')
+            [CompletionResult]::new('--translator', 'translator', [CompletionResultType]::ParameterName, 'Name of a translator')
+            [CompletionResult]::new('--illustrator', 'illustrator', [CompletionResultType]::ParameterName, 'Name of an illustrator')
+            [CompletionResult]::new('--description', 'description', [CompletionResultType]::ParameterName, 'Description of the greentext')
+            [CompletionResult]::new('--publisher', 'publisher', [CompletionResultType]::ParameterName, 'Name of the publisher')
+            [CompletionResult]::new('--language', 'language', [CompletionResultType]::ParameterName, 'Language of the greentext as a BCP 47 tag, like `en` or `es-419` [default: en]')
+            [CompletionResult]::new('--date', 'date', [CompletionResultType]::ParameterName, 'Date of publication as `YYYY`, `YYYY-MM` or `YYYY-MM-DD` [default: date of the build]')
+            [CompletionResult]::new('--rights', 'rights', [CompletionResultType]::ParameterName, 'Copyright or license of the greentext')
+            [CompletionResult]::new('--source', 'source', [CompletionResultType]::ParameterName, 'Where the greentext comes from, like the URL of the thread')
+            [CompletionResult]::new('--identifier', 'identifier', [CompletionResultType]::ParameterName, 'Unique identifier of the book, like an ISBN or URL [default: a UUID]')
+            [CompletionResult]::new('--series', 'series', [CompletionResultType]::ParameterName, 'Name of the series the greentext is part of')
+            [CompletionResult]::new('--series-index', 'series-index', [CompletionResultType]::ParameterName, 'Position of the greentext in its series, like `2` or `2.5`')
+            [CompletionResult]::new('--metadata', 'metadata', [CompletionResultType]::ParameterName, 'Manifest to take the metadata of the book from, like a sidecar file kept along with the chapters')
+            [CompletionResult]::new('--theme', 'theme', [CompletionResultType]::ParameterName, 'Look of the book [default: default]')
+            [CompletionResult]::new('--green-color', 'green-color', [CompletionResultType]::ParameterName, 'RGB color of the green highlight in hexadecimal notation [default: the one of the theme, #2CAF26 in the default one]')
+            [CompletionResult]::new('--spoiler-color', 'spoiler-color', [CompletionResultType]::ParameterName, 'RGB color of the spoiler highlight in hexadecimal notation, the one of their outline in the e-ink theme [default: the one of the theme, #000 in the default one]')
+            [CompletionResult]::new('--orange-color', 'orange-color', [CompletionResultType]::ParameterName, 'Highlight lines starting with `<` with the given RGB color in hexadecimal notation')
+            [CompletionResult]::new('--caret-color', 'caret-color', [CompletionResultType]::ParameterName, 'Highlight lines starting with `^` with the given RGB color in hexadecimal notation')
+            [CompletionResult]::new('--spoiler-syntax', 'spoiler-syntax', [CompletionResultType]::ParameterName, 'Syntax recognized as a spoiler [default: bbcode]')
+            [CompletionResult]::new('--spoiler-mode', 'spoiler-mode', [CompletionResultType]::ParameterName, 'How spoilers are rendered [default: blackout]')
+            [CompletionResult]::new('--stylesheet', 'stylesheet', [CompletionResultType]::ParameterName, 'Stylesheet added after the generated rules of the book')
+            [CompletionResult]::new('--css-var', 'css-var', [CompletionResultType]::ParameterName, 'Value of a CSS variable of the generated rules, like `post-spacing=2em`')
+            [CompletionResult]::new('--chapter-title', 'chapter-title', [CompletionResultType]::ParameterName, 'Title of the chapter with the given number, counting from 1')
+            [CompletionResult]::new('--chapter-title-regex', 'chapter-title-regex', [CompletionResultType]::ParameterName, 'Regular expression matched against the file name of each chapter, its title is made from the capture groups')
+            [CompletionResult]::new('--input-format', 'input-format', [CompletionResultType]::ParameterName, 'Format of the input files [default: auto]')
+            [CompletionResult]::new('--op-match', 'op-match', [CompletionResultType]::ParameterName, 'How posts by the OP are recognized in threads, the rest are left out [default: auto]')
+            [CompletionResult]::new('--post', 'post', [CompletionResultType]::ParameterName, 'Number of a post to keep from threads instead of the ones by the OP')
+            [CompletionResult]::new('--stdin-title', 'stdin-title', [CompletionResultType]::ParameterName, 'Title of the chapter read from standard input when `-` is given as a file [default: stdin]')
+            [CompletionResult]::new('--sort', 'sort', [CompletionResultType]::ParameterName, 'Order of the files found in directories and glob patterns given as files [default: natural]')
+            [CompletionResult]::new('--color', 'color', [CompletionResultType]::ParameterName, 'When to use colors')
+            [CompletionResult]::new('-t', 't', [CompletionResultType]::ParameterName, 'Title of the greentext')
+            [CompletionResult]::new('--title', 'title', [CompletionResultType]::ParameterName, 'Title of the greentext')
+            [CompletionResult]::new('-a', 'a', [CompletionResultType]::ParameterName, 'Name of the author')
+            [CompletionResult]::new('--author', 'author', [CompletionResultType]::ParameterName, 'Name of the author')
+            [CompletionResult]::new('-o', 'o', [CompletionResultType]::ParameterName, 'Path for the generated epub file')
+            [CompletionResult]::new('--output', 'output', [CompletionResultType]::ParameterName, 'Path for the generated epub file')
+            [CompletionResult]::new('-h', 'h', [CompletionResultType]::ParameterName, 'Print help information')
+            [CompletionResult]::new('--help', 'help', [CompletionResultType]::ParameterName, 'Print help information')
+            [CompletionResult]::new('-V', 'V', [CompletionResultType]::ParameterName, 'Print version information')
+            [CompletionResult]::new('--version', 'version', [CompletionResultType]::ParameterName, 'Print version information')
+            [CompletionResult]::new('--no-generated-cover', 'no-generated-cover', [CompletionResultType]::ParameterName, 'Leave the book without a cover when none is given instead of generating one')
+            [CompletionResult]::new('--normalize-cover', 'normalize-cover', [CompletionResultType]::ParameterName, 'Convert the cover image to JPEG or PNG, scaling it down to fit the largest size and leaving out its EXIF data')
+            [CompletionResult]::new('--chapter-title-first-line', 'chapter-title-first-line', [CompletionResultType]::ParameterName, 'Take the title of each chapter from the first line of its file, which is left out of it')
+            [CompletionResult]::new('--no-post-headers', 'no-post-headers', [CompletionResultType]::ParameterName, 'Leave out the header with the author, time and number at the start of each post')
+            [CompletionResult]::new('--reproducible', 'reproducible', [CompletionResultType]::ParameterName, 'Generate the same bytes every time for the same inputs, with the modification date taken from `SOURCE_DATE_EPOCH` or else 1980-01-01')
+            [CompletionResult]::new('-v', 'v', [CompletionResultType]::ParameterName, 'Shows verbose output, can be used multiple times to set level of verbosity')
+            [CompletionResult]::new('--verbose', 'verbose', [CompletionResultType]::ParameterName, 'Shows verbose output, can be used multiple times to set level of verbosity')
+            [CompletionResult]::new('-q', 'q', [CompletionResultType]::ParameterName, 'Supress all output')
+            [CompletionResult]::new('--quiet', 'quiet', [CompletionResultType]::ParameterName, 'Supress all output')
+            [CompletionResult]::new('append', 'append', [CompletionResultType]::ParameterValue, 'Add chapters to an EPUB built by green2epub')
+            [CompletionResult]::new('build', 'build', [CompletionResultType]::ParameterValue, 'Create an EPUB from a manifest')
+            [CompletionResult]::new('check', 'check', [CompletionResultType]::ParameterValue, 'Report problems in text files in greentext format without creating an EPUB')
+            [CompletionResult]::new('extract', 'extract', [CompletionResultType]::ParameterValue, 'Write the chapters of an EPUB built by green2epub back as text files, along with a manifest to build it again')
+            [CompletionResult]::new('help', 'help', [CompletionResultType]::ParameterValue, 'Print this message or the help of the given subcommand(s)')
+            break
+        }
+        'green2epub;append' {
             [CompletionResult]::new('-t', 't', [CompletionResultType]::ParameterName, 'Title of the greentext')
             [CompletionResult]::new('--title', 'title', [CompletionResultType]::ParameterName, 'Title of the greentext')
             [CompletionResult]::new('-a', 'a', [CompletionResultType]::ParameterName, 'Name of the author')
             [CompletionResult]::new('--author', 'author', [CompletionResultType]::ParameterName, 'Name of the author')
             [CompletionResult]::new('-c', 'c', [CompletionResultType]::ParameterName, 'Cover image to use')
             [CompletionResult]::new('--cover', 'cover', [CompletionResultType]::ParameterName, 'Cover image to use')
+            [CompletionResult]::new('--cover-layout', 'cover-layout', [CompletionResultType]::ParameterName, 'Layout of the cover generated for books without one [default: centered]')
+            [CompletionResult]::new('--cover-colors', 'cover-colors', [CompletionResultType]::ParameterName, 'Colors of the cover generated for books without one [default: greentext]')
+            [CompletionResult]::new('--cover-format', 'cover-format', [CompletionResultType]::ParameterName, 'Format the cover image is converted to [default: jpeg]')
+            [CompletionResult]::new('--cover-max-size', 'cover-max-size', [CompletionResultType]::ParameterName, 'Largest size of the cover image as `WIDTHxHEIGHT`, larger images are scaled down to fit keeping their aspect ratio [default: 1600x2400]')
+            [CompletionResult]::new('--cover-quality', 'cover-quality', [CompletionResultType]::ParameterName, 'Quality of the cover image when converted to JPEG, from 1 to 100 [default: 85]')
             [CompletionResult]::new('-s', 's', [CompletionResultType]::ParameterName, 'Greentext subjects/tags')
             [CompletionResult]::new('--subject', 'subject', [CompletionResultType]::ParameterName, 'Greentext subjects/tags')
             [CompletionResult]::new('--tag', 'tag', [CompletionResultType]::ParameterName, 'Greentext subjects/tags')
-            [CompletionResult]::new('--green-color', 'green-color', [CompletionResultType]::ParameterName, 'RGB color of the green highlight in hexadecimal notation')
-            [CompletionResult]::new('--spoiler-color', 'spoiler-color', [CompletionResultType]::ParameterName, 'RGB color of the spoiler highlight in hexadecimal notation')
+            [CompletionResult]::new('--author-file-as', 'author-file-as', [CompletionResultType]::ParameterName, 'Name an author is sorted by, like `Doe, John`')
+            [CompletionResult]::new('--editor', 'editor', [CompletionResultType]::ParameterName, 'Name of an editor')
+            [CompletionResult]::new('--translator', 'translator', [CompletionResultType]::ParameterName, 'Name of a translator')
+            [CompletionResult]::new('--illustrator', 'illustrator', [CompletionResultType]::ParameterName, 'Name of an illustrator')
+            [CompletionResult]::new('--description', 'description', [CompletionResultType]::ParameterName, 'Description of the greentext')
+            [CompletionResult]::new('--publisher', 'publisher', [CompletionResultType]::ParameterName, 'Name of the publisher')
+            [CompletionResult]::new('--language', 'language', [CompletionResultType]::ParameterName, 'Language of the greentext as a BCP 47 tag, like `en` or `es-419` [default: en]')
+            [CompletionResult]::new('--date', 'date', [CompletionResultType]::ParameterName, 'Date of publication as `YYYY`, `YYYY-MM` or `YYYY-MM-DD` [default: date of the build]')
+            [CompletionResult]::new('--rights', 'rights', [CompletionResultType]::ParameterName, 'Copyright or license of the greentext')
+            [CompletionResult]::new('--source', 'source', [CompletionResultType]::ParameterName, 'Where the greentext comes from, like the URL of the thread')
+            [CompletionResult]::new('--identifier', 'identifier', [CompletionResultType]::ParameterName, 'Unique identifier of the book, like an ISBN or URL [default: a UUID]')
+            [CompletionResult]::new('--series', 'series', [CompletionResultType]::ParameterName, 'Name of the series the greentext is part of')
+            [CompletionResult]::new('--series-index', 'series-index', [CompletionResultType]::ParameterName, 'Position of the greentext in its series, like `2` or `2.5`')
+            [CompletionResult]::new('--metadata', 'metadata', [CompletionResultType]::ParameterName, 'Manifest to take the metadata of the book from, like a sidecar file kept along with the chapters')
+            [CompletionResult]::new('-o', 'o', [CompletionResultType]::ParameterName, 'Path for the generated epub file')
+            [CompletionResult]::new('--output', 'output', [CompletionResultType]::ParameterName, 'Path for the generated epub file')
+            [CompletionResult]::new('--theme', 'theme', [CompletionResultType]::ParameterName, 'Look of the book [default: default]')
+            [CompletionResult]::new('--green-color', 'green-color', [CompletionResultType]::ParameterName, 'RGB color of the green highlight in hexadecimal notation [default: the one of the theme, #2CAF26 in the default one]')
+            [CompletionResult]::new('--spoiler-color', 'spoiler-color', [CompletionResultType]::ParameterName, 'RGB color of the spoiler highlight in hexadecimal notation, the one of their outline in the e-ink theme [default: the one of the theme, #000 in the default one]')
+            [CompletionResult]::new('--orange-color', 'orange-color', [CompletionResultType]::ParameterName, 'Highlight lines starting with `<` with the given RGB color in hexadecimal notation')
+            [CompletionResult]::new('--caret-color', 'caret-color', [CompletionResultType]::ParameterName, 'Highlight lines starting with `^` with the given RGB color in hexadecimal notation')
+            [CompletionResult]::new('--spoiler-syntax', 'spoiler-syntax', [CompletionResultType]::ParameterName, 'Syntax recognized as a spoiler [default: bbcode]')
+            [CompletionResult]::new('--spoiler-mode', 'spoiler-mode', [CompletionResultType]::ParameterName, 'How spoilers are rendered [default: blackout]')
+            [CompletionResult]::new('--stylesheet', 'stylesheet', [CompletionResultType]::ParameterName, 'Stylesheet added after the generated rules of the book')
+            [CompletionResult]::new('--css-var', 'css-var', [CompletionResultType]::ParameterName, 'Value of a CSS variable of the generated rules, like `post-spacing=2em`')
+            [CompletionResult]::new('--chapter-title', 'chapter-title', [CompletionResultType]::ParameterName, 'Title of the chapter with the given number, counting from 1')
+            [CompletionResult]::new('--chapter-title-regex', 'chapter-title-regex', [CompletionResultType]::ParameterName, 'Regular expression matched against the file name of each chapter, its title is made from the capture groups')
+            [CompletionResult]::new('--input-format', 'input-format', [CompletionResultType]::ParameterName, 'Format of the input files [default: auto]')
+            [CompletionResult]::new('--op-match', 'op-match', [CompletionResultType]::ParameterName, 'How posts by the OP are recognized in threads, the rest are left out [default: auto]')
+            [CompletionResult]::new('--post', 'post', [CompletionResultType]::ParameterName, 'Number of a post to keep from threads instead of the ones by the OP')
+            [CompletionResult]::new('--stdin-title', 'stdin-title', [CompletionResultType]::ParameterName, 'Title of the chapter read from standard input when `-` is given as a file [default: stdin]')
+            [CompletionResult]::new('--sort', 'sort', [CompletionResultType]::ParameterName, 'Order of the files found in directories and glob patterns given as files [default: natural]')
             [CompletionResult]::new('--color', 'color', [CompletionResultType]::ParameterName, 'When to use colors')
+            [CompletionResult]::new('--no-generated-cover', 'no-generated-cover', [CompletionResultType]::ParameterName, 'Leave the book without a cover when none is given instead of generating one')
+            [CompletionResult]::new('--normalize-cover', 'normalize-cover', [CompletionResultType]::ParameterName, 'Convert the cover image to JPEG or PNG, scaling it down to fit the largest size and leaving out its EXIF data')
+            [CompletionResult]::new('--chapter-title-first-line', 'chapter-title-first-line', [CompletionResultType]::ParameterName, 'Take the title of each chapter from the first line of its file, which is left out of it')
+            [CompletionResult]::new('--no-post-headers', 'no-post-headers', [CompletionResultType]::ParameterName, 'Leave out the header with the author, time and number at the start of each post')
+            [CompletionResult]::new('--reproducible', 'reproducible', [CompletionResultType]::ParameterName, 'Generate the same bytes every time for the same inputs, with the modification date taken from `SOURCE_DATE_EPOCH` or else 1980-01-01')
+            [CompletionResult]::new('-h', 'h', [CompletionResultType]::ParameterName, 'Print help information')
+            [CompletionResult]::new('--help', 'help', [CompletionResultType]::ParameterName, 'Print help information')
+            [CompletionResult]::new('-v', 'v', [CompletionResultType]::ParameterName, 'Shows verbose output, can be used multiple times to set level of verbosity')
+            [CompletionResult]::new('--verbose', 'verbose', [CompletionResultType]::ParameterName, 'Shows verbose output, can be used multiple times to set level of verbosity')
+            [CompletionResult]::new('-q', 'q', [CompletionResultType]::ParameterName, 'Supress all output')
+            [CompletionResult]::new('--quiet', 'quiet', [CompletionResultType]::ParameterName, 'Supress all output')
+            break
+        }
+        'green2epub;build' {
+            [CompletionResult]::new('-t', 't', [CompletionResultType]::ParameterName, 'Title of the greentext')
+            [CompletionResult]::new('--title', 'title', [CompletionResultType]::ParameterName, 'Title of the greentext')
+            [CompletionResult]::new('-a', 'a', [CompletionResultType]::ParameterName, 'Name of the author')
+            [CompletionResult]::new('--author', 'author', [CompletionResultType]::ParameterName, 'Name of the author')
+            [CompletionResult]::new('-c', 'c', [CompletionResultType]::ParameterName, 'Cover image to use')
+            [CompletionResult]::new('--cover', 'cover', [CompletionResultType]::ParameterName, 'Cover image to use')
+            [CompletionResult]::new('--cover-layout', 'cover-layout', [CompletionResultType]::ParameterName, 'Layout of the cover generated for books without one [default: centered]')
+            [CompletionResult]::new('--cover-colors', 'cover-colors', [CompletionResultType]::ParameterName, 'Colors of the cover generated for books without one [default: greentext]')
+            [CompletionResult]::new('--cover-format', 'cover-format', [CompletionResultType]::ParameterName, 'Format the cover image is converted to [default: jpeg]')
+            [CompletionResult]::new('--cover-max-size', 'cover-max-size', [CompletionResultType]::ParameterName, 'Largest size of the cover image as `WIDTHxHEIGHT`, larger images are scaled down to fit keeping their aspect ratio [default: 1600x2400]')
+            [CompletionResult]::new('--cover-quality', 'cover-quality', [CompletionResultType]::ParameterName, 'Quality of the cover image when converted to JPEG, from 1 to 100 [default: 85]')
+            [CompletionResult]::new('-s', 's', [CompletionResultType]::ParameterName, 'Greentext subjects/tags')
+            [CompletionResult]::new('--subject', 'subject', [CompletionResultType]::ParameterName, 'Greentext subjects/tags')
+            [CompletionResult]::new('--tag', 'tag', [CompletionResultType]::ParameterName, 'Greentext subjects/tags')
+            [CompletionResult]::new('--author-file-as', 'author-file-as', [CompletionResultType]::ParameterName, 'Name an author is sorted by, like `Doe, John`')
+            [CompletionResult]::new('--editor', 'editor', [CompletionResultType]::ParameterName, 'Name of an editor')
+            [CompletionResult]::new('--translator', 'translator', [CompletionResultType]::ParameterName, 'Name of a translator')
+            [CompletionResult]::new('--illustrator', 'illustrator', [CompletionResultType]::ParameterName, 'Name of an illustrator')
+            [CompletionResult]::new('--description', 'description', [CompletionResultType]::ParameterName, 'Description of the greentext')
+            [CompletionResult]::new('--publisher', 'publisher', [CompletionResultType]::ParameterName, 'Name of the publisher')
+            [CompletionResult]::new('--language', 'language', [CompletionResultType]::ParameterName, 'Language of the greentext as a BCP 47 tag, like `en` or `es-419` [default: en]')
+            [CompletionResult]::new('--date', 'date', [CompletionResultType]::ParameterName, 'Date of publication as `YYYY`, `YYYY-MM` or `YYYY-MM-DD` [default: date of the build]')
+            [CompletionResult]::new('--rights', 'rights', [CompletionResultType]::ParameterName, 'Copyright or license of the greentext')
+            [CompletionResult]::new('--source', 'source', [CompletionResultType]::ParameterName, 'Where the greentext comes from, like the URL of the thread')
+            [CompletionResult]::new('--identifier', 'identifier', [CompletionResultType]::ParameterName, 'Unique identifier of the book, like an ISBN or URL [default: a UUID]')
+            [CompletionResult]::new('--series', 'series', [CompletionResultType]::ParameterName, 'Name of the series the greentext is part of')
+            [CompletionResult]::new('--series-index', 'series-index', [CompletionResultType]::ParameterName, 'Position of the greentext in its series, like `2` or `2.5`')
+            [CompletionResult]::new('--metadata', 'metadata', [CompletionResultType]::ParameterName, 'Manifest to take the metadata of the book from, like a sidecar file kept along with the chapters')
             [CompletionResult]::new('-o', 'o', [CompletionResultType]::ParameterName, 'Path for the generated epub file')
             [CompletionResult]::new('--output', 'output', [CompletionResultType]::ParameterName, 'Path for the generated epub file')
+            [CompletionResult]::new('--theme', 'theme', [CompletionResultType]::ParameterName, 'Look of the book [default: default]')
+            [CompletionResult]::new('--green-color', 'green-color', [CompletionResultType]::ParameterName, 'RGB color of the green highlight in hexadecimal notation [default: the one of the theme, #2CAF26 in the default one]')
+            [CompletionResult]::new('--spoiler-color', 'spoiler-color', [CompletionResultType]::ParameterName, 'RGB color of the spoiler highlight in hexadecimal notation, the one of their outline in the e-ink theme [default: the one of the theme, #000 in the default one]')
+            [CompletionResult]::new('--orange-color', 'orange-color', [CompletionResultType]::ParameterName, 'Highlight lines starting with `<` with the given RGB color in hexadecimal notation')
+            [CompletionResult]::new('--caret-color', 'caret-color', [CompletionResultType]::ParameterName, 'Highlight lines starting with `^` with the given RGB color in hexadecimal notation')
+            [CompletionResult]::new('--spoiler-syntax', 'spoiler-syntax', [CompletionResultType]::ParameterName, 'Syntax recognized as a spoiler [default: bbcode]')
+            [CompletionResult]::new('--spoiler-mode', 'spoiler-mode', [CompletionResultType]::ParameterName, 'How spoilers are rendered [default: blackout]')
+            [CompletionResult]::new('--stylesheet', 'stylesheet', [CompletionResultType]::ParameterName, 'Stylesheet added after the generated rules of the book')
+            [CompletionResult]::new('--css-var', 'css-var', [CompletionResultType]::ParameterName, 'Value of a CSS variable of the generated rules, like `post-spacing=2em`')
+            [CompletionResult]::new('--chapter-title', 'chapter-title', [CompletionResultType]::ParameterName, 'Title of the chapter with the given number, counting from 1')
+            [CompletionResult]::new('--chapter-title-regex', 'chapter-title-regex', [CompletionResultType]::ParameterName, 'Regular expression matched against the file name of each chapter, its title is made from the capture groups')
+            [CompletionResult]::new('--input-format', 'input-format', [CompletionResultType]::ParameterName, 'Format of the input files [default: auto]')
+            [CompletionResult]::new('--op-match', 'op-match', [CompletionResultType]::ParameterName, 'How posts by the OP are recognized in threads, the rest are left out [default: auto]')
+            [CompletionResult]::new('--post', 'post', [CompletionResultType]::ParameterName, 'Number of a post to keep from threads instead of the ones by the OP')
+            [CompletionResult]::new('--stdin-title', 'stdin-title', [CompletionResultType]::ParameterName, 'Title of the chapter read from standard input when `-` is given as a file [default: stdin]')
+            [CompletionResult]::new('--sort', 'sort', [CompletionResultType]::ParameterName, 'Order of the files found in directories and glob patterns given as files [default: natural]')
+            [CompletionResult]::new('--color', 'color', [CompletionResultType]::ParameterName, 'When to use colors')
+            [CompletionResult]::new('--no-generated-cover', 'no-generated-cover', [CompletionResultType]::ParameterName, 'Leave the book without a cover when none is given instead of generating one')
+            [CompletionResult]::new('--normalize-cover', 'normalize-cover', [CompletionResultType]::ParameterName, 'Convert the cover image to JPEG or PNG, scaling it down to fit the largest size and leaving out its EXIF data')
+            [CompletionResult]::new('--chapter-title-first-line', 'chapter-title-first-line', [CompletionResultType]::ParameterName, 'Take the title of each chapter from the first line of its file, which is left out of it')
+            [CompletionResult]::new('--no-post-headers', 'no-post-headers', [CompletionResultType]::ParameterName, 'Leave out the header with the author, time and number at the start of each post')
+            [CompletionResult]::new('--reproducible', 'reproducible', [CompletionResultType]::ParameterName, 'Generate the same bytes every time for the same inputs, with the modification date taken from `SOURCE_DATE_EPOCH` or else 1980-01-01')
             [CompletionResult]::new('-h', 'h', [CompletionResultType]::ParameterName, 'Print help information')
             [CompletionResult]::new('--help', 'help', [CompletionResultType]::ParameterName, 'Print help information')
-            [CompletionResult]::new('-V', 'V', [CompletionResultType]::ParameterName, 'Print version information')
-            [CompletionResult]::new('--version', 'version', [CompletionResultType]::ParameterName, 'Print version information')
+            [CompletionResult]::new('-v', 'v', [CompletionResultType]::ParameterName, 'Shows verbose output, can be used multiple times to set level of verbosity')
+            [CompletionResult]::new('--verbose', 'verbose', [CompletionResultType]::ParameterName, 'Shows verbose output, can be used multiple times to set level of verbosity')
+            [CompletionResult]::new('-q', 'q', [CompletionResultType]::ParameterName, 'Supress all output')
+            [CompletionResult]::new('--quiet', 'quiet', [CompletionResultType]::ParameterName, 'Supress all output')
+            break
+        }
+        'green2epub;check' {
+            [CompletionResult]::new('--format', 'format', [CompletionResultType]::ParameterName, 'Format of the report')
+            [CompletionResult]::new('--spoiler-syntax', 'spoiler-syntax', [CompletionResultType]::ParameterName, 'Syntax recognized as a spoiler')
+            [CompletionResult]::new('--color', 'color', [CompletionResultType]::ParameterName, 'When to use colors')
+            [CompletionResult]::new('-h', 'h', [CompletionResultType]::ParameterName, 'Print help information')
+            [CompletionResult]::new('--help', 'help', [CompletionResultType]::ParameterName, 'Print help information')
+            [CompletionResult]::new('-v', 'v', [CompletionResultType]::ParameterName, 'Shows verbose output, can be used multiple times to set level of verbosity')
+            [CompletionResult]::new('--verbose', 'verbose', [CompletionResultType]::ParameterName, 'Shows verbose output, can be used multiple times to set level of verbosity')
+            [CompletionResult]::new('-q', 'q', [CompletionResultType]::ParameterName, 'Supress all output')
+            [CompletionResult]::new('--quiet', 'quiet', [CompletionResultType]::ParameterName, 'Supress all output')
+            break
+        }
+        'green2epub;extract' {
+            [CompletionResult]::new('-d', 'd', [CompletionResultType]::ParameterName, 'Directory to write the text files, cover and manifest to [default: name of the EPUB without extension]')
+            [CompletionResult]::new('--directory', 'directory', [CompletionResultType]::ParameterName, 'Directory to write the text files, cover and manifest to [default: name of the EPUB without extension]')
+            [CompletionResult]::new('--color', 'color', [CompletionResultType]::ParameterName, 'When to use colors')
+            [CompletionResult]::new('-h', 'h', [CompletionResultType]::ParameterName, 'Print help information')
+            [CompletionResult]::new('--help', 'help', [CompletionResultType]::ParameterName, 'Print help information')
+            [CompletionResult]::new('-v', 'v', [CompletionResultType]::ParameterName, 'Shows verbose output, can be used multiple times to set level of verbosity')
+            [CompletionResult]::new('--verbose', 'verbose', [CompletionResultType]::ParameterName, 'Shows verbose output, can be used multiple times to set level of verbosity')
+            [CompletionResult]::new('-q', 'q', [CompletionResultType]::ParameterName, 'Supress all output')
+            [CompletionResult]::new('--quiet', 'quiet', [CompletionResultType]::ParameterName, 'Supress all output')
+            break
+        }
+        'green2epub;help' {
+            [CompletionResult]::new('--color', 'color', [CompletionResultType]::ParameterName, 'When to use colors')
             [CompletionResult]::new('-v', 'v', [CompletionResultType]::ParameterName, 'Shows verbose output, can be used multiple times to set level of verbosity')
             [CompletionResult]::new('--verbose', 'verbose', [CompletionResultType]::ParameterName, 'Shows verbose output, can be used multiple times to set level of verbosity')
             [CompletionResult]::new('-q', 'q', [CompletionResultType]::ParameterName, 'Supress all output')
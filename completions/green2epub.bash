@@ -12,6 +12,21 @@ _green2epub() {
             "$1")
                 cmd="green2epub"
                 ;;
+            append)
+                cmd+="__append"
+                ;;
+            build)
+                cmd+="__build"
+                ;;
+            check)
+                cmd+="__check"
+                ;;
+            extract)
+                cmd+="__extract"
+                ;;
+            help)
+                cmd+="__help"
+                ;;
             *)
                 ;;
         esac
@@ -19,11 +34,213 @@ _green2epub() {
 
     case "${cmd}" in
         green2epub)
-            opts="-h -V -t -a -c -s -v -q -o --help --version --title --author --cover --tag --subject --green-color --spoiler-color --verbose --quiet --color --output <FILE>..."
+            opts="-h -V -c -s -v -q -t -a -o --help --version --cover --cover-layout --cover-colors --no-generated-cover --normalize-cover --cover-format --cover-max-size --cover-quality --tag --subject --author-file-as --editor --translator --illustrator --description --publisher --language --date --rights --source --identifier --series --series-index --metadata --theme --green-color --spoiler-color --orange-color --caret-color --spoiler-syntax --spoiler-mode --stylesheet --css-var --chapter-title --chapter-title-regex --chapter-title-first-line --input-format --op-match --post --no-post-headers --stdin-title --sort --reproducible --verbose --quiet --color --title --author --output <FILE>... append build check extract help"
             if [[ ${cur} == -* || ${COMP_CWORD} -eq 1 ]] ; then
                 COMPREPLY=( $(compgen -W "${opts}" -- "${cur}") )
                 return 0
             fi
+            case "${prev}" in
+                --cover)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                -c)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --cover-layout)
+                    COMPREPLY=($(compgen -W "centered band corner" -- "${cur}"))
+                    return 0
+                    ;;
+                --cover-colors)
+                    COMPREPLY=($(compgen -W "greentext light dark yotsuba" -- "${cur}"))
+                    return 0
+                    ;;
+                --cover-format)
+                    COMPREPLY=($(compgen -W "jpeg png" -- "${cur}"))
+                    return 0
+                    ;;
+                --cover-max-size)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --cover-quality)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --subject)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --tag)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                -s)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --author-file-as)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --editor)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --translator)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --illustrator)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --description)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --publisher)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --language)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --date)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --rights)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --source)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --identifier)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --series)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --series-index)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --metadata)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --theme)
+                    COMPREPLY=($(compgen -W "default yotsuba dark eink" -- "${cur}"))
+                    return 0
+                    ;;
+                --green-color)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --spoiler-color)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --orange-color)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --caret-color)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --spoiler-syntax)
+                    COMPREPLY=($(compgen -W "bbcode bbcode-short discord reddit" -- "${cur}"))
+                    return 0
+                    ;;
+                --spoiler-mode)
+                    COMPREPLY=($(compgen -W "blackout footnote endnote inline" -- "${cur}"))
+                    return 0
+                    ;;
+                --stylesheet)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --css-var)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --chapter-title)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --chapter-title-regex)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --input-format)
+                    COMPREPLY=($(compgen -W "auto text 4chan-json archive-html" -- "${cur}"))
+                    return 0
+                    ;;
+                --op-match)
+                    COMPREPLY=($(compgen -W "auto name trip id" -- "${cur}"))
+                    return 0
+                    ;;
+                --post)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --stdin-title)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --sort)
+                    COMPREPLY=($(compgen -W "natural lexical mtime date" -- "${cur}"))
+                    return 0
+                    ;;
+                --color)
+                    COMPREPLY=($(compgen -W "auto always never" -- "${cur}"))
+                    return 0
+                    ;;
+                --title)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                -t)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --author)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                -a)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --output)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                -o)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                *)
+                    COMPREPLY=()
+                    ;;
+            esac
+            COMPREPLY=( $(compgen -W "${opts}" -- "${cur}") )
+            return 0
+            ;;
+        green2epub__append)
+            opts="-t -a -c -s -o -h -v -q --title --author --cover --cover-layout --cover-colors --no-generated-cover --normalize-cover --cover-format --cover-max-size --cover-quality --tag --subject --author-file-as --editor --translator --illustrator --description --publisher --language --date --rights --source --identifier --series --series-index --metadata --output --theme --green-color --spoiler-color --orange-color --caret-color --spoiler-syntax --spoiler-mode --stylesheet --css-var --chapter-title --chapter-title-regex --chapter-title-first-line --input-format --op-match --post --no-post-headers --stdin-title --sort --reproducible --help --verbose --quiet --color <EPUB> <FILE>..."
+            if [[ ${cur} == -* || ${COMP_CWORD} -eq 2 ]] ; then
+                COMPREPLY=( $(compgen -W "${opts}" -- "${cur}") )
+                return 0
+            fi
             case "${prev}" in
                 --title)
                     COMPREPLY=($(compgen -f "${cur}"))
@@ -49,6 +266,26 @@ _green2epub() {
                     COMPREPLY=($(compgen -f "${cur}"))
                     return 0
                     ;;
+                --cover-layout)
+                    COMPREPLY=($(compgen -W "centered band corner" -- "${cur}"))
+                    return 0
+                    ;;
+                --cover-colors)
+                    COMPREPLY=($(compgen -W "greentext light dark yotsuba" -- "${cur}"))
+                    return 0
+                    ;;
+                --cover-format)
+                    COMPREPLY=($(compgen -W "jpeg png" -- "${cur}"))
+                    return 0
+                    ;;
+                --cover-max-size)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --cover-quality)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
                 --subject)
                     COMPREPLY=($(compgen -f "${cur}"))
                     return 0
@@ -61,6 +298,74 @@ _green2epub() {
                     COMPREPLY=($(compgen -f "${cur}"))
                     return 0
                     ;;
+                --author-file-as)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --editor)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --translator)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --illustrator)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --description)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --publisher)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --language)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --date)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --rights)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --source)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --identifier)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --series)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --series-index)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --metadata)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --output)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                -o)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --theme)
+                    COMPREPLY=($(compgen -W "default yotsuba dark eink" -- "${cur}"))
+                    return 0
+                    ;;
                 --green-color)
                     COMPREPLY=($(compgen -f "${cur}"))
                     return 0
@@ -69,10 +374,188 @@ _green2epub() {
                     COMPREPLY=($(compgen -f "${cur}"))
                     return 0
                     ;;
+                --orange-color)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --caret-color)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --spoiler-syntax)
+                    COMPREPLY=($(compgen -W "bbcode bbcode-short discord reddit" -- "${cur}"))
+                    return 0
+                    ;;
+                --spoiler-mode)
+                    COMPREPLY=($(compgen -W "blackout footnote endnote inline" -- "${cur}"))
+                    return 0
+                    ;;
+                --stylesheet)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --css-var)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --chapter-title)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --chapter-title-regex)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --input-format)
+                    COMPREPLY=($(compgen -W "auto text 4chan-json archive-html" -- "${cur}"))
+                    return 0
+                    ;;
+                --op-match)
+                    COMPREPLY=($(compgen -W "auto name trip id" -- "${cur}"))
+                    return 0
+                    ;;
+                --post)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --stdin-title)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --sort)
+                    COMPREPLY=($(compgen -W "natural lexical mtime date" -- "${cur}"))
+                    return 0
+                    ;;
                 --color)
                     COMPREPLY=($(compgen -W "auto always never" -- "${cur}"))
                     return 0
                     ;;
+                *)
+                    COMPREPLY=()
+                    ;;
+            esac
+            COMPREPLY=( $(compgen -W "${opts}" -- "${cur}") )
+            return 0
+            ;;
+        green2epub__build)
+            opts="-t -a -c -s -o -h -v -q --title --author --cover --cover-layout --cover-colors --no-generated-cover --normalize-cover --cover-format --cover-max-size --cover-quality --tag --subject --author-file-as --editor --translator --illustrator --description --publisher --language --date --rights --source --identifier --series --series-index --metadata --output --theme --green-color --spoiler-color --orange-color --caret-color --spoiler-syntax --spoiler-mode --stylesheet --css-var --chapter-title --chapter-title-regex --chapter-title-first-line --input-format --op-match --post --no-post-headers --stdin-title --sort --reproducible --help --verbose --quiet --color <MANIFEST>"
+            if [[ ${cur} == -* || ${COMP_CWORD} -eq 2 ]] ; then
+                COMPREPLY=( $(compgen -W "${opts}" -- "${cur}") )
+                return 0
+            fi
+            case "${prev}" in
+                --title)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                -t)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --author)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                -a)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --cover)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                -c)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --cover-layout)
+                    COMPREPLY=($(compgen -W "centered band corner" -- "${cur}"))
+                    return 0
+                    ;;
+                --cover-colors)
+                    COMPREPLY=($(compgen -W "greentext light dark yotsuba" -- "${cur}"))
+                    return 0
+                    ;;
+                --cover-format)
+                    COMPREPLY=($(compgen -W "jpeg png" -- "${cur}"))
+                    return 0
+                    ;;
+                --cover-max-size)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --cover-quality)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --subject)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --tag)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                -s)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --author-file-as)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --editor)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --translator)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --illustrator)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --description)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --publisher)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --language)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --date)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --rights)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --source)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --identifier)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --series)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --series-index)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --metadata)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
                 --output)
                     COMPREPLY=($(compgen -f "${cur}"))
                     return 0
@@ -81,6 +564,144 @@ _green2epub() {
                     COMPREPLY=($(compgen -f "${cur}"))
                     return 0
                     ;;
+                --theme)
+                    COMPREPLY=($(compgen -W "default yotsuba dark eink" -- "${cur}"))
+                    return 0
+                    ;;
+                --green-color)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --spoiler-color)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --orange-color)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --caret-color)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --spoiler-syntax)
+                    COMPREPLY=($(compgen -W "bbcode bbcode-short discord reddit" -- "${cur}"))
+                    return 0
+                    ;;
+                --spoiler-mode)
+                    COMPREPLY=($(compgen -W "blackout footnote endnote inline" -- "${cur}"))
+                    return 0
+                    ;;
+                --stylesheet)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --css-var)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --chapter-title)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --chapter-title-regex)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --input-format)
+                    COMPREPLY=($(compgen -W "auto text 4chan-json archive-html" -- "${cur}"))
+                    return 0
+                    ;;
+                --op-match)
+                    COMPREPLY=($(compgen -W "auto name trip id" -- "${cur}"))
+                    return 0
+                    ;;
+                --post)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --stdin-title)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --sort)
+                    COMPREPLY=($(compgen -W "natural lexical mtime date" -- "${cur}"))
+                    return 0
+                    ;;
+                --color)
+                    COMPREPLY=($(compgen -W "auto always never" -- "${cur}"))
+                    return 0
+                    ;;
+                *)
+                    COMPREPLY=()
+                    ;;
+            esac
+            COMPREPLY=( $(compgen -W "${opts}" -- "${cur}") )
+            return 0
+            ;;
+        green2epub__check)
+            opts="-h -v -q --format --spoiler-syntax --help --verbose --quiet --color <FILE>..."
+            if [[ ${cur} == -* || ${COMP_CWORD} -eq 2 ]] ; then
+                COMPREPLY=( $(compgen -W "${opts}" -- "${cur}") )
+                return 0
+            fi
+            case "${prev}" in
+                --format)
+                    COMPREPLY=($(compgen -W "human json" -- "${cur}"))
+                    return 0
+                    ;;
+                --spoiler-syntax)
+                    COMPREPLY=($(compgen -W "bbcode bbcode-short discord reddit" -- "${cur}"))
+                    return 0
+                    ;;
+                --color)
+                    COMPREPLY=($(compgen -W "auto always never" -- "${cur}"))
+                    return 0
+                    ;;
+                *)
+                    COMPREPLY=()
+                    ;;
+            esac
+            COMPREPLY=( $(compgen -W "${opts}" -- "${cur}") )
+            return 0
+            ;;
+        green2epub__extract)
+            opts="-d -h -v -q --directory --help --verbose --quiet --color <EPUB>"
+            if [[ ${cur} == -* || ${COMP_CWORD} -eq 2 ]] ; then
+                COMPREPLY=( $(compgen -W "${opts}" -- "${cur}") )
+                return 0
+            fi
+            case "${prev}" in
+                --directory)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                -d)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --color)
+                    COMPREPLY=($(compgen -W "auto always never" -- "${cur}"))
+                    return 0
+                    ;;
+                *)
+                    COMPREPLY=()
+                    ;;
+            esac
+            COMPREPLY=( $(compgen -W "${opts}" -- "${cur}") )
+            return 0
+            ;;
+        green2epub__help)
+            opts="-v -q --verbose --quiet --color"
+            if [[ ${cur} == -* || ${COMP_CWORD} -eq 2 ]] ; then
+                COMPREPLY=( $(compgen -W "${opts}" -- "${cur}") )
+                return 0
+            fi
+            case "${prev}" in
+                --color)
+                    COMPREPLY=($(compgen -W "auto always never" -- "${cur}"))
+                    return 0
+                    ;;
                 *)
                     COMPREPLY=()
                     ;;
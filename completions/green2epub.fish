@@ -1,12 +1,167 @@
-complete -c green2epub -s t -l title -d 'Title of the greentext' -r
-complete -c green2epub -s a -l author -d 'Name of the author' -r
-complete -c green2epub -s c -l cover -d 'Cover image to use' -r -F
-complete -c green2epub -s s -l subject -l tag -d 'Greentext subjects/tags' -r
-complete -c green2epub -l green-color -d 'RGB color of the green highlight in hexadecimal notation' -r
-complete -c green2epub -l spoiler-color -d 'RGB color of the spoiler highlight in hexadecimal notation' -r
-complete -c green2epub -l color -d 'When to use colors' -r -f -a "{auto	,always	,never	}"
-complete -c green2epub -s o -l output -d 'Path for the generated epub file' -r -F
-complete -c green2epub -s h -l help -d 'Print help information'
-complete -c green2epub -s V -l version -d 'Print version information'
-complete -c green2epub -s v -l verbose -d 'Shows verbose output, can be used multiple times to set level of verbosity'
-complete -c green2epub -s q -l quiet -d 'Supress all output'
+complete -c green2epub -n "__fish_use_subcommand" -s c -l cover -d 'Cover image to use' -r -F
+complete -c green2epub -n "__fish_use_subcommand" -l cover-layout -d 'Layout of the cover generated for books without one [default: centered]' -r -f -a "{centered	Title in the middle with the authors under it, inside a frame,band	Title on a band across the cover,corner	Title at the bottom left, next to a bar}"
+complete -c green2epub -n "__fish_use_subcommand" -l cover-colors -d 'Colors of the cover generated for books without one [default: greentext]' -r -f -a "{greentext	Green color of the book on a dark background,light	Dark text on a white background,dark	Light text on a dark background,yotsuba	Colors of the imageboard pages with a cream background}"
+complete -c green2epub -n "__fish_use_subcommand" -l cover-format -d 'Format the cover image is converted to [default: jpeg]' -r -f -a "{jpeg	,png	}"
+complete -c green2epub -n "__fish_use_subcommand" -l cover-max-size -d 'Largest size of the cover image as `WIDTHxHEIGHT`, larger images are scaled down to fit keeping their aspect ratio [default: 1600x2400]' -r
+complete -c green2epub -n "__fish_use_subcommand" -l cover-quality -d 'Quality of the cover image when converted to JPEG, from 1 to 100 [default: 85]' -r
+complete -c green2epub -n "__fish_use_subcommand" -s s -l subject -l tag -d 'Greentext subjects/tags' -r
+complete -c green2epub -n "__fish_use_subcommand" -l author-file-as -d 'Name an author is sorted by, like `Doe, John`' -r
+complete -c green2epub -n "__fish_use_subcommand" -l editor -d 'Name of an editor' -r
+complete -c green2epub -n "__fish_use_subcommand" -l translator -d 'Name of a translator' -r
+complete -c green2epub -n "__fish_use_subcommand" -l illustrator -d 'Name of an illustrator' -r
+complete -c green2epub -n "__fish_use_subcommand" -l description -d 'Description of the greentext' -r
+complete -c green2epub -n "__fish_use_subcommand" -l publisher -d 'Name of the publisher' -r
+complete -c green2epub -n "__fish_use_subcommand" -l language -d 'Language of the greentext as a BCP 47 tag, like `en` or `es-419` [default: en]' -r
+complete -c green2epub -n "__fish_use_subcommand" -l date -d 'Date of publication as `YYYY`, `YYYY-MM` or `YYYY-MM-DD` [default: date of the build]' -r
+complete -c green2epub -n "__fish_use_subcommand" -l rights -d 'Copyright or license of the greentext' -r
+complete -c green2epub -n "__fish_use_subcommand" -l source -d 'Where the greentext comes from, like the URL of the thread' -r
+complete -c green2epub -n "__fish_use_subcommand" -l identifier -d 'Unique identifier of the book, like an ISBN or URL [default: a UUID]' -r
+complete -c green2epub -n "__fish_use_subcommand" -l series -d 'Name of the series the greentext is part of' -r
+complete -c green2epub -n "__fish_use_subcommand" -l series-index -d 'Position of the greentext in its series, like `2` or `2.5`' -r
+complete -c green2epub -n "__fish_use_subcommand" -l metadata -d 'Manifest to take the metadata of the book from, like a sidecar file kept along with the chapters' -r -F
+complete -c green2epub -n "__fish_use_subcommand" -l theme -d 'Look of the book [default: default]' -r -f -a "{default	Green text on the background of the reader,yotsuba	Colors of the imageboard pages with a cream background,dark	Light text on a dark background,eink	Greentext marked with a border and spoilers with a dotted outline instead of colors, for e-ink readers}"
+complete -c green2epub -n "__fish_use_subcommand" -l green-color -d 'RGB color of the green highlight in hexadecimal notation [default: the one of the theme, #2CAF26 in the default one]' -r
+complete -c green2epub -n "__fish_use_subcommand" -l spoiler-color -d 'RGB color of the spoiler highlight in hexadecimal notation, the one of their outline in the e-ink theme [default: the one of the theme, #000 in the default one]' -r
+complete -c green2epub -n "__fish_use_subcommand" -l orange-color -d 'Highlight lines starting with `<` with the given RGB color in hexadecimal notation' -r
+complete -c green2epub -n "__fish_use_subcommand" -l caret-color -d 'Highlight lines starting with `^` with the given RGB color in hexadecimal notation' -r
+complete -c green2epub -n "__fish_use_subcommand" -l spoiler-syntax -d 'Syntax recognized as a spoiler [default: bbcode]' -r -f -a "{bbcode	`[spoiler]text[/spoiler]`,bbcode-short	`[s]text[/s]`, takes precedence over strikethrough,discord	`||text||`,reddit	`>!text!<`}"
+complete -c green2epub -n "__fish_use_subcommand" -l spoiler-mode -d 'How spoilers are rendered [default: blackout]' -r -f -a "{blackout	Text hidden with the same color as its background,footnote	Text moved to popup footnotes,endnote	Text moved to endnotes at the end of the chapter,inline	Text left as is, enclosed in brackets}"
+complete -c green2epub -n "__fish_use_subcommand" -l stylesheet -d 'Stylesheet added after the generated rules of the book' -r -F
+complete -c green2epub -n "__fish_use_subcommand" -l css-var -d 'Value of a CSS variable of the generated rules, like `post-spacing=2em`' -r
+complete -c green2epub -n "__fish_use_subcommand" -l chapter-title -d 'Title of the chapter with the given number, counting from 1' -r
+complete -c green2epub -n "__fish_use_subcommand" -l chapter-title-regex -d 'Regular expression matched against the file name of each chapter, its title is made from the capture groups' -r
+complete -c green2epub -n "__fish_use_subcommand" -l input-format -d 'Format of the input files [default: auto]' -r -f -a "{auto	Recognized from the file extension, `.json` for 4chan API threads, `.html` for archived threads and text otherwise,text	Text in greentext format,4chan-json	Thread saved in the JSON format of the 4chan API,archive-html	Thread page saved from an archive running FoolFuuka, like desuarchive}"
+complete -c green2epub -n "__fish_use_subcommand" -l op-match -d 'How posts by the OP are recognized in threads, the rest are left out [default: auto]' -r -f -a "{auto	Tripcode if the OP has one, otherwise ID if the board shows them and name as a last resort,name	Same name as the OP,trip	Same tripcode as the OP,id	Same ID as the OP}"
+complete -c green2epub -n "__fish_use_subcommand" -l post -d 'Number of a post to keep from threads instead of the ones by the OP' -r
+complete -c green2epub -n "__fish_use_subcommand" -l stdin-title -d 'Title of the chapter read from standard input when `-` is given as a file [default: stdin]' -r
+complete -c green2epub -n "__fish_use_subcommand" -l sort -d 'Order of the files found in directories and glob patterns given as files [default: natural]' -r -f -a "{natural	File names, with numbers compared by their value so `2.txt` comes before `10.txt`,lexical	File names, character by character,mtime	Time the files were last modified, oldest first,date	Date written in the file names as `YYYY-MM-DD` or `YYYYMMDD`, oldest first}"
+complete -c green2epub -n "__fish_use_subcommand" -l color -d 'When to use colors' -r -f -a "{auto	,always	,never	}"
+complete -c green2epub -n "__fish_use_subcommand" -s t -l title -d 'Title of the greentext' -r
+complete -c green2epub -n "__fish_use_subcommand" -s a -l author -d 'Name of the author' -r
+complete -c green2epub -n "__fish_use_subcommand" -s o -l output -d 'Path for the generated epub file' -r -F
+complete -c green2epub -n "__fish_use_subcommand" -s h -l help -d 'Print help information'
+complete -c green2epub -n "__fish_use_subcommand" -s V -l version -d 'Print version information'
+complete -c green2epub -n "__fish_use_subcommand" -l no-generated-cover -d 'Leave the book without a cover when none is given instead of generating one'
+complete -c green2epub -n "__fish_use_subcommand" -l normalize-cover -d 'Convert the cover image to JPEG or PNG, scaling it down to fit the largest size and leaving out its EXIF data'
+complete -c green2epub -n "__fish_use_subcommand" -l chapter-title-first-line -d 'Take the title of each chapter from the first line of its file, which is left out of it'
+complete -c green2epub -n "__fish_use_subcommand" -l no-post-headers -d 'Leave out the header with the author, time and number at the start of each post'
+complete -c green2epub -n "__fish_use_subcommand" -l reproducible -d 'Generate the same bytes every time for the same inputs, with the modification date taken from `SOURCE_DATE_EPOCH` or else 1980-01-01'
+complete -c green2epub -n "__fish_use_subcommand" -s v -l verbose -d 'Shows verbose output, can be used multiple times to set level of verbosity'
+complete -c green2epub -n "__fish_use_subcommand" -s q -l quiet -d 'Supress all output'
+complete -c green2epub -n "__fish_use_subcommand" -f -a "append" -d 'Add chapters to an EPUB built by green2epub'
+complete -c green2epub -n "__fish_use_subcommand" -f -a "build" -d 'Create an EPUB from a manifest'
+complete -c green2epub -n "__fish_use_subcommand" -f -a "check" -d 'Report problems in text files in greentext format without creating an EPUB'
+complete -c green2epub -n "__fish_use_subcommand" -f -a "extract" -d 'Write the chapters of an EPUB built by green2epub back as text files, along with a manifest to build it again'
+complete -c green2epub -n "__fish_use_subcommand" -f -a "help" -d 'Print this message or the help of the given subcommand(s)'
+complete -c green2epub -n "__fish_seen_subcommand_from append" -s t -l title -d 'Title of the greentext' -r
+complete -c green2epub -n "__fish_seen_subcommand_from append" -s a -l author -d 'Name of the author' -r
+complete -c green2epub -n "__fish_seen_subcommand_from append" -s c -l cover -d 'Cover image to use' -r -F
+complete -c green2epub -n "__fish_seen_subcommand_from append" -l cover-layout -d 'Layout of the cover generated for books without one [default: centered]' -r -f -a "{centered	Title in the middle with the authors under it, inside a frame,band	Title on a band across the cover,corner	Title at the bottom left, next to a bar}"
+complete -c green2epub -n "__fish_seen_subcommand_from append" -l cover-colors -d 'Colors of the cover generated for books without one [default: greentext]' -r -f -a "{greentext	Green color of the book on a dark background,light	Dark text on a white background,dark	Light text on a dark background,yotsuba	Colors of the imageboard pages with a cream background}"
+complete -c green2epub -n "__fish_seen_subcommand_from append" -l cover-format -d 'Format the cover image is converted to [default: jpeg]' -r -f -a "{jpeg	,png	}"
+complete -c green2epub -n "__fish_seen_subcommand_from append" -l cover-max-size -d 'Largest size of the cover image as `WIDTHxHEIGHT`, larger images are scaled down to fit keeping their aspect ratio [default: 1600x2400]' -r
+complete -c green2epub -n "__fish_seen_subcommand_from append" -l cover-quality -d 'Quality of the cover image when converted to JPEG, from 1 to 100 [default: 85]' -r
+complete -c green2epub -n "__fish_seen_subcommand_from append" -s s -l subject -l tag -d 'Greentext subjects/tags' -r
+complete -c green2epub -n "__fish_seen_subcommand_from append" -l author-file-as -d 'Name an author is sorted by, like `Doe, John`' -r
+complete -c green2epub -n "__fish_seen_subcommand_from append" -l editor -d 'Name of an editor' -r
+complete -c green2epub -n "__fish_seen_subcommand_from append" -l translator -d 'Name of a translator' -r
+complete -c green2epub -n "__fish_seen_subcommand_from append" -l illustrator -d 'Name of an illustrator' -r
+complete -c green2epub -n "__fish_seen_subcommand_from append" -l description -d 'Description of the greentext' -r
+complete -c green2epub -n "__fish_seen_subcommand_from append" -l publisher -d 'Name of the publisher' -r
+complete -c green2epub -n "__fish_seen_subcommand_from append" -l language -d 'Language of the greentext as a BCP 47 tag, like `en` or `es-419` [default: en]' -r
+complete -c green2epub -n "__fish_seen_subcommand_from append" -l date -d 'Date of publication as `YYYY`, `YYYY-MM` or `YYYY-MM-DD` [default: date of the build]' -r
+complete -c green2epub -n "__fish_seen_subcommand_from append" -l rights -d 'Copyright or license of the greentext' -r
+complete -c green2epub -n "__fish_seen_subcommand_from append" -l source -d 'Where the greentext comes from, like the URL of the thread' -r
+complete -c green2epub -n "__fish_seen_subcommand_from append" -l identifier -d 'Unique identifier of the book, like an ISBN or URL [default: a UUID]' -r
+complete -c green2epub -n "__fish_seen_subcommand_from append" -l series -d 'Name of the series the greentext is part of' -r
+complete -c green2epub -n "__fish_seen_subcommand_from append" -l series-index -d 'Position of the greentext in its series, like `2` or `2.5`' -r
+complete -c green2epub -n "__fish_seen_subcommand_from append" -l metadata -d 'Manifest to take the metadata of the book from, like a sidecar file kept along with the chapters' -r -F
+complete -c green2epub -n "__fish_seen_subcommand_from append" -s o -l output -d 'Path for the generated epub file' -r -F
+complete -c green2epub -n "__fish_seen_subcommand_from append" -l theme -d 'Look of the book [default: default]' -r -f -a "{default	Green text on the background of the reader,yotsuba	Colors of the imageboard pages with a cream background,dark	Light text on a dark background,eink	Greentext marked with a border and spoilers with a dotted outline instead of colors, for e-ink readers}"
+complete -c green2epub -n "__fish_seen_subcommand_from append" -l green-color -d 'RGB color of the green highlight in hexadecimal notation [default: the one of the theme, #2CAF26 in the default one]' -r
+complete -c green2epub -n "__fish_seen_subcommand_from append" -l spoiler-color -d 'RGB color of the spoiler highlight in hexadecimal notation, the one of their outline in the e-ink theme [default: the one of the theme, #000 in the default one]' -r
+complete -c green2epub -n "__fish_seen_subcommand_from append" -l orange-color -d 'Highlight lines starting with `<` with the given RGB color in hexadecimal notation' -r
+complete -c green2epub -n "__fish_seen_subcommand_from append" -l caret-color -d 'Highlight lines starting with `^` with the given RGB color in hexadecimal notation' -r
+complete -c green2epub -n "__fish_seen_subcommand_from append" -l spoiler-syntax -d 'Syntax recognized as a spoiler [default: bbcode]' -r -f -a "{bbcode	`[spoiler]text[/spoiler]`,bbcode-short	`[s]text[/s]`, takes precedence over strikethrough,discord	`||text||`,reddit	`>!text!<`}"
+complete -c green2epub -n "__fish_seen_subcommand_from append" -l spoiler-mode -d 'How spoilers are rendered [default: blackout]' -r -f -a "{blackout	Text hidden with the same color as its background,footnote	Text moved to popup footnotes,endnote	Text moved to endnotes at the end of the chapter,inline	Text left as is, enclosed in brackets}"
+complete -c green2epub -n "__fish_seen_subcommand_from append" -l stylesheet -d 'Stylesheet added after the generated rules of the book' -r -F
+complete -c green2epub -n "__fish_seen_subcommand_from append" -l css-var -d 'Value of a CSS variable of the generated rules, like `post-spacing=2em`' -r
+complete -c green2epub -n "__fish_seen_subcommand_from append" -l chapter-title -d 'Title of the chapter with the given number, counting from 1' -r
+complete -c green2epub -n "__fish_seen_subcommand_from append" -l chapter-title-regex -d 'Regular expression matched against the file name of each chapter, its title is made from the capture groups' -r
+complete -c green2epub -n "__fish_seen_subcommand_from append" -l input-format -d 'Format of the input files [default: auto]' -r -f -a "{auto	Recognized from the file extension, `.json` for 4chan API threads, `.html` for archived threads and text otherwise,text	Text in greentext format,4chan-json	Thread saved in the JSON format of the 4chan API,archive-html	Thread page saved from an archive running FoolFuuka, like desuarchive}"
+complete -c green2epub -n "__fish_seen_subcommand_from append" -l op-match -d 'How posts by the OP are recognized in threads, the rest are left out [default: auto]' -r -f -a "{auto	Tripcode if the OP has one, otherwise ID if the board shows them and name as a last resort,name	Same name as the OP,trip	Same tripcode as the OP,id	Same ID as the OP}"
+complete -c green2epub -n "__fish_seen_subcommand_from append" -l post -d 'Number of a post to keep from threads instead of the ones by the OP' -r
+complete -c green2epub -n "__fish_seen_subcommand_from append" -l stdin-title -d 'Title of the chapter read from standard input when `-` is given as a file [default: stdin]' -r
+complete -c green2epub -n "__fish_seen_subcommand_from append" -l sort -d 'Order of the files found in directories and glob patterns given as files [default: natural]' -r -f -a "{natural	File names, with numbers compared by their value so `2.txt` comes before `10.txt`,lexical	File names, character by character,mtime	Time the files were last modified, oldest first,date	Date written in the file names as `YYYY-MM-DD` or `YYYYMMDD`, oldest first}"
+complete -c green2epub -n "__fish_seen_subcommand_from append" -l color -d 'When to use colors' -r -f -a "{auto	,always	,never	}"
+complete -c green2epub -n "__fish_seen_subcommand_from append" -l no-generated-cover -d 'Leave the book without a cover when none is given instead of generating one'
+complete -c green2epub -n "__fish_seen_subcommand_from append" -l normalize-cover -d 'Convert the cover image to JPEG or PNG, scaling it down to fit the largest size and leaving out its EXIF data'
+complete -c green2epub -n "__fish_seen_subcommand_from append" -l chapter-title-first-line -d 'Take the title of each chapter from the first line of its file, which is left out of it'
+complete -c green2epub -n "__fish_seen_subcommand_from append" -l no-post-headers -d 'Leave out the header with the author, time and number at the start of each post'
+complete -c green2epub -n "__fish_seen_subcommand_from append" -l reproducible -d 'Generate the same bytes every time for the same inputs, with the modification date taken from `SOURCE_DATE_EPOCH` or else 1980-01-01'
+complete -c green2epub -n "__fish_seen_subcommand_from append" -s h -l help -d 'Print help information'
+complete -c green2epub -n "__fish_seen_subcommand_from append" -s v -l verbose -d 'Shows verbose output, can be used multiple times to set level of verbosity'
+complete -c green2epub -n "__fish_seen_subcommand_from append" -s q -l quiet -d 'Supress all output'
+complete -c green2epub -n "__fish_seen_subcommand_from build" -s t -l title -d 'Title of the greentext' -r
+complete -c green2epub -n "__fish_seen_subcommand_from build" -s a -l author -d 'Name of the author' -r
+complete -c green2epub -n "__fish_seen_subcommand_from build" -s c -l cover -d 'Cover image to use' -r -F
+complete -c green2epub -n "__fish_seen_subcommand_from build" -l cover-layout -d 'Layout of the cover generated for books without one [default: centered]' -r -f -a "{centered	Title in the middle with the authors under it, inside a frame,band	Title on a band across the cover,corner	Title at the bottom left, next to a bar}"
+complete -c green2epub -n "__fish_seen_subcommand_from build" -l cover-colors -d 'Colors of the cover generated for books without one [default: greentext]' -r -f -a "{greentext	Green color of the book on a dark background,light	Dark text on a white background,dark	Light text on a dark background,yotsuba	Colors of the imageboard pages with a cream background}"
+complete -c green2epub -n "__fish_seen_subcommand_from build" -l cover-format -d 'Format the cover image is converted to [default: jpeg]' -r -f -a "{jpeg	,png	}"
+complete -c green2epub -n "__fish_seen_subcommand_from build" -l cover-max-size -d 'Largest size of the cover image as `WIDTHxHEIGHT`, larger images are scaled down to fit keeping their aspect ratio [default: 1600x2400]' -r
+complete -c green2epub -n "__fish_seen_subcommand_from build" -l cover-quality -d 'Quality of the cover image when converted to JPEG, from 1 to 100 [default: 85]' -r
+complete -c green2epub -n "__fish_seen_subcommand_from build" -s s -l subject -l tag -d 'Greentext subjects/tags' -r
+complete -c green2epub -n "__fish_seen_subcommand_from build" -l author-file-as -d 'Name an author is sorted by, like `Doe, John`' -r
+complete -c green2epub -n "__fish_seen_subcommand_from build" -l editor -d 'Name of an editor' -r
+complete -c green2epub -n "__fish_seen_subcommand_from build" -l translator -d 'Name of a translator' -r
+complete -c green2epub -n "__fish_seen_subcommand_from build" -l illustrator -d 'Name of an illustrator' -r
+complete -c green2epub -n "__fish_seen_subcommand_from build" -l description -d 'Description of the greentext' -r
+complete -c green2epub -n "__fish_seen_subcommand_from build" -l publisher -d 'Name of the publisher' -r
+complete -c green2epub -n "__fish_seen_subcommand_from build" -l language -d 'Language of the greentext as a BCP 47 tag, like `en` or `es-419` [default: en]' -r
+complete -c green2epub -n "__fish_seen_subcommand_from build" -l date -d 'Date of publication as `YYYY`, `YYYY-MM` or `YYYY-MM-DD` [default: date of the build]' -r
+complete -c green2epub -n "__fish_seen_subcommand_from build" -l rights -d 'Copyright or license of the greentext' -r
+complete -c green2epub -n "__fish_seen_subcommand_from build" -l source -d 'Where the greentext comes from, like the URL of the thread' -r
+complete -c green2epub -n "__fish_seen_subcommand_from build" -l identifier -d 'Unique identifier of the book, like an ISBN or URL [default: a UUID]' -r
+complete -c green2epub -n "__fish_seen_subcommand_from build" -l series -d 'Name of the series the greentext is part of' -r
+complete -c green2epub -n "__fish_seen_subcommand_from build" -l series-index -d 'Position of the greentext in its series, like `2` or `2.5`' -r
+complete -c green2epub -n "__fish_seen_subcommand_from build" -l metadata -d 'Manifest to take the metadata of the book from, like a sidecar file kept along with the chapters' -r -F
+complete -c green2epub -n "__fish_seen_subcommand_from build" -s o -l output -d 'Path for the generated epub file' -r -F
+complete -c green2epub -n "__fish_seen_subcommand_from build" -l theme -d 'Look of the book [default: default]' -r -f -a "{default	Green text on the background of the reader,yotsuba	Colors of the imageboard pages with a cream background,dark	Light text on a dark background,eink	Greentext marked with a border and spoilers with a dotted outline instead of colors, for e-ink readers}"
+complete -c green2epub -n "__fish_seen_subcommand_from build" -l green-color -d 'RGB color of the green highlight in hexadecimal notation [default: the one of the theme, #2CAF26 in the default one]' -r
+complete -c green2epub -n "__fish_seen_subcommand_from build" -l spoiler-color -d 'RGB color of the spoiler highlight in hexadecimal notation, the one of their outline in the e-ink theme [default: the one of the theme, #000 in the default one]' -r
+complete -c green2epub -n "__fish_seen_subcommand_from build" -l orange-color -d 'Highlight lines starting with `<` with the given RGB color in hexadecimal notation' -r
+complete -c green2epub -n "__fish_seen_subcommand_from build" -l caret-color -d 'Highlight lines starting with `^` with the given RGB color in hexadecimal notation' -r
+complete -c green2epub -n "__fish_seen_subcommand_from build" -l spoiler-syntax -d 'Syntax recognized as a spoiler [default: bbcode]' -r -f -a "{bbcode	`[spoiler]text[/spoiler]`,bbcode-short	`[s]text[/s]`, takes precedence over strikethrough,discord	`||text||`,reddit	`>!text!<`}"
+complete -c green2epub -n "__fish_seen_subcommand_from build" -l spoiler-mode -d 'How spoilers are rendered [default: blackout]' -r -f -a "{blackout	Text hidden with the same color as its background,footnote	Text moved to popup footnotes,endnote	Text moved to endnotes at the end of the chapter,inline	Text left as is, enclosed in brackets}"
+complete -c green2epub -n "__fish_seen_subcommand_from build" -l stylesheet -d 'Stylesheet added after the generated rules of the book' -r -F
+complete -c green2epub -n "__fish_seen_subcommand_from build" -l css-var -d 'Value of a CSS variable of the generated rules, like `post-spacing=2em`' -r
+complete -c green2epub -n "__fish_seen_subcommand_from build" -l chapter-title -d 'Title of the chapter with the given number, counting from 1' -r
+complete -c green2epub -n "__fish_seen_subcommand_from build" -l chapter-title-regex -d 'Regular expression matched against the file name of each chapter, its title is made from the capture groups' -r
+complete -c green2epub -n "__fish_seen_subcommand_from build" -l input-format -d 'Format of the input files [default: auto]' -r -f -a "{auto	Recognized from the file extension, `.json` for 4chan API threads, `.html` for archived threads and text otherwise,text	Text in greentext format,4chan-json	Thread saved in the JSON format of the 4chan API,archive-html	Thread page saved from an archive running FoolFuuka, like desuarchive}"
+complete -c green2epub -n "__fish_seen_subcommand_from build" -l op-match -d 'How posts by the OP are recognized in threads, the rest are left out [default: auto]' -r -f -a "{auto	Tripcode if the OP has one, otherwise ID if the board shows them and name as a last resort,name	Same name as the OP,trip	Same tripcode as the OP,id	Same ID as the OP}"
+complete -c green2epub -n "__fish_seen_subcommand_from build" -l post -d 'Number of a post to keep from threads instead of the ones by the OP' -r
+complete -c green2epub -n "__fish_seen_subcommand_from build" -l stdin-title -d 'Title of the chapter read from standard input when `-` is given as a file [default: stdin]' -r
+complete -c green2epub -n "__fish_seen_subcommand_from build" -l sort -d 'Order of the files found in directories and glob patterns given as files [default: natural]' -r -f -a "{natural	File names, with numbers compared by their value so `2.txt` comes before `10.txt`,lexical	File names, character by character,mtime	Time the files were last modified, oldest first,date	Date written in the file names as `YYYY-MM-DD` or `YYYYMMDD`, oldest first}"
+complete -c green2epub -n "__fish_seen_subcommand_from build" -l color -d 'When to use colors' -r -f -a "{auto	,always	,never	}"
+complete -c green2epub -n "__fish_seen_subcommand_from build" -l no-generated-cover -d 'Leave the book without a cover when none is given instead of generating one'
+complete -c green2epub -n "__fish_seen_subcommand_from build" -l normalize-cover -d 'Convert the cover image to JPEG or PNG, scaling it down to fit the largest size and leaving out its EXIF data'
+complete -c green2epub -n "__fish_seen_subcommand_from build" -l chapter-title-first-line -d 'Take the title of each chapter from the first line of its file, which is left out of it'
+complete -c green2epub -n "__fish_seen_subcommand_from build" -l no-post-headers -d 'Leave out the header with the author, time and number at the start of each post'
+complete -c green2epub -n "__fish_seen_subcommand_from build" -l reproducible -d 'Generate the same bytes every time for the same inputs, with the modification date taken from `SOURCE_DATE_EPOCH` or else 1980-01-01'
+complete -c green2epub -n "__fish_seen_subcommand_from build" -s h -l help -d 'Print help information'
+complete -c green2epub -n "__fish_seen_subcommand_from build" -s v -l verbose -d 'Shows verbose output, can be used multiple times to set level of verbosity'
+complete -c green2epub -n "__fish_seen_subcommand_from build" -s q -l quiet -d 'Supress all output'
+complete -c green2epub -n "__fish_seen_subcommand_from check" -l format -d 'Format of the report' -r -f -a "{human	One problem per line as `FILE:LINE:COLUMN: MESSAGE`,json	A JSON array of problems}"
+complete -c green2epub -n "__fish_seen_subcommand_from check" -l spoiler-syntax -d 'Syntax recognized as a spoiler' -r -f -a "{bbcode	`[spoiler]text[/spoiler]`,bbcode-short	`[s]text[/s]`, takes precedence over strikethrough,discord	`||text||`,reddit	`>!text!<`}"
+complete -c green2epub -n "__fish_seen_subcommand_from check" -l color -d 'When to use colors' -r -f -a "{auto	,always	,never	}"
+complete -c green2epub -n "__fish_seen_subcommand_from check" -s h -l help -d 'Print help information'
+complete -c green2epub -n "__fish_seen_subcommand_from check" -s v -l verbose -d 'Shows verbose output, can be used multiple times to set level of verbosity'
+complete -c green2epub -n "__fish_seen_subcommand_from check" -s q -l quiet -d 'Supress all output'
+complete -c green2epub -n "__fish_seen_subcommand_from extract" -s d -l directory -d 'Directory to write the text files, cover and manifest to [default: name of the EPUB without extension]' -r -f -a "(__fish_complete_directories)"
+complete -c green2epub -n "__fish_seen_subcommand_from extract" -l color -d 'When to use colors' -r -f -a "{auto	,always	,never	}"
+complete -c green2epub -n "__fish_seen_subcommand_from extract" -s h -l help -d 'Print help information'
+complete -c green2epub -n "__fish_seen_subcommand_from extract" -s v -l verbose -d 'Shows verbose output, can be used multiple times to set level of verbosity'
+complete -c green2epub -n "__fish_seen_subcommand_from extract" -s q -l quiet -d 'Supress all output'
+complete -c green2epub -n "__fish_seen_subcommand_from help" -l color -d 'When to use colors' -r -f -a "{auto	,always	,never	}"
+complete -c green2epub -n "__fish_seen_subcommand_from help" -s v -l verbose -d 'Shows verbose output, can be used multiple times to set level of verbosity'
+complete -c green2epub -n "__fish_seen_subcommand_from help" -s q -l quiet -d 'Supress all output'
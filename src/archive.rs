@@ -0,0 +1,335 @@
+//! Threads saved as HTML pages of archives running FoolFuuka, like desuarchive.
+
+use scraper::{node::Node, ElementRef, Html, Selector};
+
+use crate::book::Post;
+use crate::errors::{CliError, CliResult};
+use crate::fourchan::{PostFilter, Poster};
+use crate::parser::MarkedLine;
+
+const DEFAULT_NAME: &str = "Anonymous";
+const POST_REF_PREFIX: &str = ">>";
+const POSTER_ID_PREFIX: &str = "ID:";
+
+fn selector(selector: &str) -> Selector {
+    Selector::parse(selector).expect("selectors are valid")
+}
+
+/// First element that matches the selector and belongs to the given post, instead of a post
+/// nested in it.
+fn find<'a>(article: ElementRef<'a>, selector: &Selector) -> Option<ElementRef<'a>> {
+    article.select(selector).find(|element| {
+        element
+            .ancestors()
+            .filter_map(ElementRef::wrap)
+            .find(|ancestor| ancestor.value().name() == "article")
+            .map(|ancestor| ancestor.id())
+            == Some(article.id())
+    })
+}
+
+fn text_of(element: ElementRef) -> String {
+    element.text().collect::<String>().trim().to_string()
+}
+
+/// Adds the content of an element to the lines, starting from the last one.
+///
+/// Inside `pre` elements, line breaks in the source start a new line as `br` elements do.
+fn add_content(element: ElementRef, lines: &mut Vec<MarkedLine>, in_pre: bool) {
+    for child in element.children() {
+        let child_element = match child.value() {
+            Node::Text(text) if in_pre => {
+                let text = text.replace('\r', "");
+                for (idx, part) in text.split('\n').enumerate() {
+                    if idx > 0 {
+                        lines.push(MarkedLine::new());
+                    }
+                    lines.last_mut().unwrap().text(part);
+                }
+                continue;
+            }
+            Node::Text(text) => {
+                // Line breaks in the source are only there for readability.
+                let mut text = text.replace(['\n', '\r'], "");
+                let line = lines.last_mut().expect("there is always a line");
+                if line.is_empty() {
+                    text = text.trim_start().to_string();
+                }
+
+                line.text(text);
+                continue;
+            }
+            Node::Element(_) => ElementRef::wrap(child).expect("node is an element"),
+            _ => continue,
+        };
+
+        let is_spoiler = match child_element.value().name() {
+            "s" => true,
+            "span" => child_element
+                .value()
+                .classes()
+                .any(|class| class == "spoiler"),
+            _ => false,
+        };
+
+        match child_element.value().name() {
+            "br" => lines.push(MarkedLine::new()),
+            _ if is_spoiler => {
+                lines.last_mut().unwrap().open_spoiler();
+                add_content(child_element, lines, in_pre);
+                lines.last_mut().unwrap().close_spoiler();
+            }
+            "pre" => {
+                lines.last_mut().unwrap().open_code();
+                add_content(child_element, lines, true);
+                // A line break before the closing tag doesn't start another line.
+                if lines.len() > 1 && lines.last().is_some_and(MarkedLine::is_empty) {
+                    lines.pop();
+                }
+                lines.last_mut().unwrap().close_code();
+            }
+            "a" => {
+                let text = text_of(child_element);
+                match text
+                    .strip_prefix(POST_REF_PREFIX)
+                    .and_then(|number| number.parse::<u64>().ok())
+                {
+                    Some(number) => {
+                        lines.last_mut().unwrap().post_ref(number);
+                    }
+                    None => add_content(child_element, lines, in_pre),
+                };
+            }
+            _ => add_content(child_element, lines, in_pre),
+        };
+    }
+}
+
+/// Lines of the body of a post, keeping its greentext, spoilers, code and post references.
+///
+/// Spoilers are either `s` elements, as 4chan marks them, or `span` elements with a `spoiler`
+/// class, as FoolFuuka does.
+pub(crate) fn body_lines(body: ElementRef) -> Vec<MarkedLine> {
+    let mut lines = vec![MarkedLine::new()];
+    add_content(body, &mut lines, false);
+
+    while lines.last().is_some_and(MarkedLine::is_empty) {
+        lines.pop();
+    }
+
+    lines
+}
+
+/// A post along with the ID of its poster, which is only used to tell posters apart.
+#[derive(Debug, Clone)]
+struct ArchivedPost {
+    post: Post,
+    id: Option<String>,
+}
+
+impl ArchivedPost {
+    fn poster(&self) -> Poster<'_> {
+        Poster {
+            name: &self.post.author,
+            trip: self.post.tripcode.as_deref(),
+            id: self.id.as_deref(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Thread {
+    subject: Option<String>,
+    posts: Vec<ArchivedPost>,
+}
+
+impl Thread {
+    /// Parses a saved thread page, where each post is an `article` element.
+    pub fn from_html(html: &str) -> CliResult<Self> {
+        let document = Html::parse_document(html);
+
+        let author = selector(".post_author");
+        let tripcode = selector(".post_tripcode");
+        let poster_id = selector(".poster_hash");
+        let time = selector("time");
+        let body = selector("div.text");
+        let subject = selector(".post_title");
+
+        let mut thread = Thread {
+            subject: None,
+            posts: Vec::new(),
+        };
+
+        for article in document.select(&selector("article.thread, article.post")) {
+            let number = match article.value().id().and_then(|id| id.parse::<u64>().ok()) {
+                Some(number) => number,
+                None => {
+                    debug!("Skipping article without a post number");
+                    continue;
+                }
+            };
+
+            if thread.posts.is_empty() {
+                thread.subject = find(article, &subject)
+                    .map(text_of)
+                    .filter(|subject| !subject.is_empty());
+            }
+
+            let post = Post {
+                number,
+                author: find(article, &author)
+                    .map(text_of)
+                    .filter(|author| !author.is_empty())
+                    .unwrap_or_else(|| DEFAULT_NAME.into()),
+                tripcode: find(article, &tripcode)
+                    .map(text_of)
+                    .filter(|tripcode| !tripcode.is_empty()),
+                time: find(article, &time).and_then(|time| {
+                    time.value()
+                        .attr("datetime")
+                        .map(String::from)
+                        .or_else(|| Some(text_of(time)))
+                }),
+                lines: find(article, &body).map(body_lines).unwrap_or_default(),
+            };
+            let id = find(article, &poster_id).map(|id| {
+                let id = text_of(id);
+                id.strip_prefix(POSTER_ID_PREFIX)
+                    .map(|id| id.trim().to_string())
+                    .unwrap_or(id)
+            });
+
+            thread.posts.push(ArchivedPost { post, id });
+        }
+
+        if thread.posts.is_empty() {
+            return Err(CliError::from(String::from(
+                "no posts found in archived thread",
+            )));
+        }
+        debug!("Thread has {} posts", thread.posts.len());
+
+        Ok(thread)
+    }
+
+    /// Subject of the thread, if the OP has one.
+    pub fn subject(&self) -> Option<&str> {
+        self.subject.as_deref()
+    }
+
+    pub fn posts(&self, filter: &PostFilter) -> Vec<Post> {
+        let op = match self.posts.first() {
+            Some(op) => op.poster(),
+            None => return Vec::new(),
+        };
+
+        let posts: Vec<Post> = self
+            .posts
+            .iter()
+            .filter(|archived| filter.keeps(archived.post.number, archived.poster(), op))
+            .map(|archived| archived.post.clone())
+            .collect();
+        debug!("Kept {} posts of the thread", posts.len());
+
+        posts
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const THREAD: &str = r##"<!DOCTYPE html>
+<html><body>
+<article class="clearfix thread" id="100">
+  <header><div class="post_data">
+    <h2 class="post_title">Green story</h2>
+    <span class="post_author">Anonymous</span>
+    <span class="post_tripcode">!Trip</span>
+    <span class="time_wrap"><time datetime="2015-06-10T21:21:43+00:00">Wed 10 Jun 2015</time></span>
+  </div></header>
+  <div class="text">
+    <span class="greentext">&gt;be me</span><br />
+    <span class="greentext">&gt;be <span class="spoiler">spoiled [b]</span></span><br />
+  </div>
+  <aside class="posts">
+    <article class="post" id="101">
+      <div class="post_wrapper">
+        <header><div class="post_data">
+          <span class="post_author">Anonymous</span>
+          <span class="poster_hash">ID:abc</span>
+        </div></header>
+        <div class="text"><a href="#100" class="backlink" data-post="100">&gt;&gt;100</a><br />nice</div>
+      </div>
+    </article>
+    <article class="post" id="102">
+      <div class="post_wrapper">
+        <header><div class="post_data">
+          <span class="post_author">Anonymous</span>
+          <span class="post_tripcode">!Trip</span>
+        </div></header>
+        <div class="text">end</div>
+      </div>
+    </article>
+  </aside>
+</article>
+</body></html>"##;
+
+    #[test]
+    fn archived_thread() {
+        let thread = Thread::from_html(THREAD).unwrap();
+        assert_eq!(thread.subject(), Some("Green story"));
+
+        let posts = thread.posts(&PostFilter::Op);
+        assert_eq!(
+            posts.iter().map(|post| post.number).collect::<Vec<u64>>(),
+            [100, 102]
+        );
+
+        let op = &posts[0];
+        assert_eq!(op.author, "Anonymous");
+        assert_eq!(op.tripcode.as_deref(), Some("!Trip"));
+        assert_eq!(op.time.as_deref(), Some("2015-06-10T21:21:43+00:00"));
+        assert_eq!(
+            op.lines,
+            [
+                MarkedLine::new().text(">be me").clone(),
+                MarkedLine::new()
+                    .text(">be ")
+                    .open_spoiler()
+                    .text("spoiled [b]")
+                    .close_spoiler()
+                    .clone(),
+            ]
+        );
+
+        let reply = &thread.posts(&PostFilter::Numbers(vec![101]))[0];
+        assert_eq!(
+            reply.lines,
+            [
+                MarkedLine::new().post_ref(100).clone(),
+                MarkedLine::new().text("nice").clone()
+            ]
+        );
+        assert_eq!(thread.posts[1].id.as_deref(), Some("abc"));
+    }
+
+    #[test]
+    fn multiline_code() {
+        let html = Html::parse_fragment("<pre>fn main() {\r\n    draw();\n}\n</pre><br>done");
+        assert_eq!(
+            body_lines(html.root_element()),
+            [
+                MarkedLine::new().open_code().text("fn main() {").clone(),
+                MarkedLine::new().text("    draw();").clone(),
+                MarkedLine::new().text("}").close_code().clone(),
+                MarkedLine::new().text("done").clone(),
+            ]
+        );
+    }
+
+    #[test]
+    fn page_without_posts() {
+        assert!(Thread::from_html("<html><body><p>404</p></body></html>").is_err());
+    }
+}
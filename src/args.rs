@@ -1,4 +1,4 @@
-use clap::{ArgEnum, Parser, ValueHint};
+use clap::{AppSettings, ArgEnum, Args as ClapArgs, Parser, Subcommand, ValueHint};
 
 #[derive(ArgEnum, Debug, Clone, Copy)]
 pub enum Color {
@@ -7,13 +7,165 @@ pub enum Color {
     Never,
 }
 
+#[allow(clippy::derivable_impls)]
 impl Default for Color {
     fn default() -> Color {
         Color::Auto
     }
 }
 
-fn hex_color_validator(color: &str) -> Result<(), String> {
+#[derive(ArgEnum, Debug, Clone, Copy)]
+pub enum Spoiler {
+    /// `[spoiler]text[/spoiler]`
+    Bbcode,
+    /// `[s]text[/s]`, takes precedence over strikethrough
+    BbcodeShort,
+    /// `||text||`
+    Discord,
+    /// `>!text!<`
+    Reddit,
+}
+
+#[derive(ArgEnum, Debug, Clone, Copy)]
+pub enum SpoilerRendering {
+    /// Text hidden with the same color as its background
+    Blackout,
+    /// Text moved to popup footnotes
+    Footnote,
+    /// Text moved to endnotes at the end of the chapter
+    Endnote,
+    /// Text left as is, enclosed in brackets
+    Inline,
+}
+
+#[derive(ArgEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputFormat {
+    /// Recognized from the file extension, `.json` for 4chan API threads, `.html` for archived
+    /// threads and text otherwise
+    Auto,
+    /// Text in greentext format
+    Text,
+    /// Thread saved in the JSON format of the 4chan API
+    #[clap(name = "4chan-json")]
+    FourchanJson,
+    /// Thread page saved from an archive running FoolFuuka, like desuarchive
+    ArchiveHtml,
+}
+
+#[derive(ArgEnum, Debug, Clone, Copy)]
+pub enum OpMatch {
+    /// Tripcode if the OP has one, otherwise ID if the board shows them and name as a last resort
+    Auto,
+    /// Same name as the OP
+    Name,
+    /// Same tripcode as the OP
+    Trip,
+    /// Same ID as the OP
+    Id,
+}
+
+#[derive(ArgEnum, Debug, Clone, Copy)]
+pub enum SortOrder {
+    /// File names, with numbers compared by their value so `2.txt` comes before `10.txt`
+    Natural,
+    /// File names, character by character
+    Lexical,
+    /// Time the files were last modified, oldest first
+    Mtime,
+    /// Date written in the file names as `YYYY-MM-DD` or `YYYYMMDD`, oldest first
+    Date,
+}
+
+#[derive(ArgEnum, Debug, Clone, Copy)]
+pub enum ReportFormat {
+    /// One problem per line as `FILE:LINE:COLUMN: MESSAGE`
+    Human,
+    /// A JSON array of problems
+    Json,
+}
+
+#[derive(ArgEnum, Debug, Clone, Copy)]
+pub enum CoverLayout {
+    /// Title in the middle with the authors under it, inside a frame
+    Centered,
+    /// Title on a band across the cover
+    Band,
+    /// Title at the bottom left, next to a bar
+    Corner,
+}
+
+#[derive(ArgEnum, Debug, Clone, Copy)]
+pub enum CoverColors {
+    /// Green color of the book on a dark background
+    Greentext,
+    /// Dark text on a white background
+    Light,
+    /// Light text on a dark background
+    Dark,
+    /// Colors of the imageboard pages with a cream background
+    Yotsuba,
+}
+
+#[derive(ArgEnum, Debug, Clone, Copy)]
+pub enum CoverFormat {
+    Jpeg,
+    Png,
+}
+
+#[derive(ArgEnum, Debug, Clone, Copy)]
+pub enum Theme {
+    /// Green text on the background of the reader
+    Default,
+    /// Colors of the imageboard pages with a cream background
+    Yotsuba,
+    /// Light text on a dark background
+    Dark,
+    /// Greentext marked with a border and spoilers with a dotted outline instead of colors,
+    /// for e-ink readers
+    Eink,
+}
+
+fn parse_chapter_title(value: &str) -> Result<(usize, String), String> {
+    let (number, title) = value
+        .split_once('=')
+        .ok_or_else(|| String::from("Expected a chapter number and its title as `N=TITLE`"))?;
+
+    let number = match number.trim().parse::<usize>() {
+        Ok(number) if number > 0 => number,
+        _ => return Err(format!("Invalid chapter number: {:?}", number)),
+    };
+
+    Ok((number, title.into()))
+}
+
+/// Parses a CSS variable as `NAME=VALUE`, where the name can have a leading `--`.
+pub fn parse_css_var(value: &str) -> Result<(String, String), String> {
+    let (name, value) = value
+        .split_once('=')
+        .ok_or_else(|| String::from("Expected a CSS variable and its value as `NAME=VALUE`"))?;
+
+    let name = name.trim();
+    let name = name.strip_prefix("--").unwrap_or(name);
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(format!("Invalid CSS variable name: {:?}", name));
+    }
+
+    let value = value.trim();
+    if value.is_empty() || value.contains([';', '{', '}']) {
+        return Err(format!(
+            "Invalid value for CSS variable {:?}: {:?}",
+            name, value
+        ));
+    }
+
+    Ok((name.into(), value.into()))
+}
+
+pub fn hex_color_validator(color: &str) -> Result<(), String> {
     let len = color.len() - 1;
     if !color.starts_with('#') || (len != 3 && len != 6) {
         return Err(
@@ -41,16 +193,95 @@ fn hex_color_validator(color: &str) -> Result<(), String> {
     Ok(())
 }
 
-#[derive(Parser, Debug)]
-#[clap(version, author)]
-/// Create an EPUB from text files in greentext format.
-pub struct Args {
+/// Checks a date in W3CDTF format, as `YYYY`, `YYYY-MM` or `YYYY-MM-DD`.
+pub fn date_validator(date: &str) -> Result<(), String> {
+    let parts: Vec<&str> = date.split('-').collect();
+    let is_number = |part: &str, len: usize| {
+        part.len() == len && part.bytes().all(|byte| byte.is_ascii_digit())
+    };
+    let in_range = |part: &str, max: u32| part.parse::<u32>().is_ok_and(|n| (1..=max).contains(&n));
+
+    let valid = match parts.as_slice() {
+        [year] => is_number(year, 4),
+        [year, month] => is_number(year, 4) && is_number(month, 2) && in_range(month, 12),
+        [year, month, day] if is_number(year, 4) && is_number(month, 2) && in_range(month, 12) => {
+            let year: u32 = year.parse().unwrap_or_default();
+            let is_leap =
+                year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400));
+            let days = match month.parse().unwrap_or_default() {
+                2 if is_leap => 29,
+                2 => 28,
+                4 | 6 | 9 | 11 => 30,
+                _ => 31,
+            };
+            is_number(day, 2) && in_range(day, days)
+        }
+        _ => false,
+    };
+
+    if valid {
+        Ok(())
+    } else {
+        Err(format!(
+            "Invalid date {:?}, it has to be either `YYYY`, `YYYY-MM` or `YYYY-MM-DD`",
+            date
+        ))
+    }
+}
+
+/// Parses the largest size of the cover as `WIDTHxHEIGHT`.
+pub fn parse_cover_size(size: &str) -> Result<(u32, u32), String> {
+    let parse = |side: &str| side.trim().parse::<u32>().ok().filter(|side| *side > 0);
+
+    match size.split_once(['x', 'X']) {
+        Some((width, height)) => match (parse(width), parse(height)) {
+            (Some(width), Some(height)) => Ok((width, height)),
+            _ => Err(format!("Invalid cover size {:?}", size)),
+        },
+        None => Err(format!(
+            "Invalid cover size {:?}, it has to be given as `WIDTHxHEIGHT` like `1600x2400`",
+            size
+        )),
+    }
+}
+
+pub fn cover_quality_validator(quality: &str) -> Result<(), String> {
+    match quality.parse::<u8>() {
+        Ok(quality) if (1..=100).contains(&quality) => Ok(()),
+        _ => Err(format!(
+            "Invalid cover quality {:?}, it has to be a number from 1 to 100",
+            quality
+        )),
+    }
+}
+
+pub fn series_index_validator(index: &str) -> Result<(), String> {
+    match index.parse::<f64>() {
+        Ok(index) if index.is_finite() && index >= 0.0 => Ok(()),
+        _ => Err(format!(
+            "Invalid series index {:?}, it has to be a number like `2` or `2.5`",
+            index
+        )),
+    }
+}
+
+/// Options of the book, all of them can be set in a manifest as well.
+#[derive(ClapArgs, Debug, Default)]
+pub struct BookArgs {
     /// Title of the greentext.
     #[clap(short, long, display_order = 1, forbid_empty_values(true))]
-    pub title: String,
+    pub title: Option<String>,
     /// Name of the author.
-    #[clap(short, long, display_order = 2, forbid_empty_values(true))]
-    pub author: String,
+    ///
+    /// Can be used multiple times to set more than one.
+    #[clap(
+        short,
+        long = "author",
+        value_name = "AUTHOR",
+        display_order = 2,
+        forbid_empty_values(true)
+    )]
+    pub authors: Vec<String>,
     /// Cover image to use.
     #[clap(
         short,
@@ -61,6 +292,43 @@ pub struct Args {
         forbid_empty_values(true)
     )]
     pub cover: Option<String>,
+    /// Layout of the cover generated for books without one [default: centered].
+    #[clap(long, arg_enum, value_name = "LAYOUT", display_order = 4)]
+    pub cover_layout: Option<CoverLayout>,
+    /// Colors of the cover generated for books without one [default: greentext].
+    ///
+    /// The greentext colors use the color given with `--green-color`.
+    #[clap(long, arg_enum, value_name = "COLORS", display_order = 5)]
+    pub cover_colors: Option<CoverColors>,
+    /// Leave the book without a cover when none is given instead of generating one.
+    #[clap(long, display_order = 6, conflicts_with("cover"))]
+    pub no_generated_cover: bool,
+    /// Convert the cover image to JPEG or PNG, scaling it down to fit the largest size and
+    /// leaving out its EXIF data.
+    ///
+    /// Giving any of `--cover-format`, `--cover-max-size` or `--cover-quality` turns this on too.
+    #[clap(long, display_order = 7)]
+    pub normalize_cover: bool,
+    /// Format the cover image is converted to [default: jpeg].
+    #[clap(long, arg_enum, value_name = "FORMAT", display_order = 8)]
+    pub cover_format: Option<CoverFormat>,
+    /// Largest size of the cover image as `WIDTHxHEIGHT`, larger images are scaled down to fit
+    /// keeping their aspect ratio [default: 1600x2400].
+    #[clap(
+        long,
+        value_name = "SIZE",
+        display_order = 9,
+        parse(try_from_str = parse_cover_size)
+    )]
+    pub cover_max_size: Option<(u32, u32)>,
+    /// Quality of the cover image when converted to JPEG, from 1 to 100 [default: 85].
+    #[clap(
+        long,
+        value_name = "QUALITY",
+        display_order = 10,
+        validator(cover_quality_validator)
+    )]
+    pub cover_quality: Option<u8>,
     /// Greentext subjects/tags.
     ///
     /// Can be used multiple times to set more than one.
@@ -69,56 +337,446 @@ pub struct Args {
         long = "subject",
         visible_alias = "tag",
         value_name = "SUBJECT",
-        display_order = 4,
+        display_order = 11,
         forbid_empty_values(true)
     )]
     pub subjects: Vec<String>,
-    /// RGB color of the green highlight in hexadecimal notation.
+    /// Name an author is sorted by, like `Doe, John`.
+    ///
+    /// Can be used multiple times, each one for the author given in the same position.
+    #[clap(
+        long,
+        value_name = "NAME",
+        display_order = 12,
+        forbid_empty_values(true)
+    )]
+    pub author_file_as: Vec<String>,
+    /// Name of an editor.
+    ///
+    /// Can be used multiple times to set more than one.
+    #[clap(
+        long = "editor",
+        value_name = "NAME",
+        display_order = 13,
+        forbid_empty_values(true)
+    )]
+    pub editors: Vec<String>,
+    /// Name of a translator.
+    ///
+    /// Can be used multiple times to set more than one.
+    #[clap(
+        long = "translator",
+        value_name = "NAME",
+        display_order = 14,
+        forbid_empty_values(true)
+    )]
+    pub translators: Vec<String>,
+    /// Name of an illustrator.
+    ///
+    /// Can be used multiple times to set more than one.
+    #[clap(
+        long = "illustrator",
+        value_name = "NAME",
+        display_order = 15,
+        forbid_empty_values(true)
+    )]
+    pub illustrators: Vec<String>,
+    /// Description of the greentext.
+    #[clap(
+        long,
+        value_name = "TEXT",
+        display_order = 16,
+        forbid_empty_values(true)
+    )]
+    pub description: Option<String>,
+    /// Name of the publisher.
+    #[clap(
+        long,
+        value_name = "NAME",
+        display_order = 17,
+        forbid_empty_values(true)
+    )]
+    pub publisher: Option<String>,
+    /// Language of the greentext as a BCP 47 tag, like `en` or `es-419` [default: en].
+    ///
+    /// Books in languages written from right to left, like `ar` or `he`, are laid out from right
+    /// to left as well.
+    #[clap(
+        long,
+        value_name = "TAG",
+        display_order = 18,
+        forbid_empty_values(true)
+    )]
+    pub language: Option<String>,
+    /// Date of publication as `YYYY`, `YYYY-MM` or `YYYY-MM-DD` [default: date of the build].
+    #[clap(
+        long,
+        value_name = "DATE",
+        display_order = 19,
+        forbid_empty_values(true),
+        validator(date_validator)
+    )]
+    pub date: Option<String>,
+    /// Copyright or license of the greentext.
+    #[clap(
+        long,
+        value_name = "TEXT",
+        display_order = 20,
+        forbid_empty_values(true)
+    )]
+    pub rights: Option<String>,
+    /// Where the greentext comes from, like the URL of the thread.
+    #[clap(
+        long,
+        value_name = "URL",
+        display_order = 21,
+        forbid_empty_values(true)
+    )]
+    pub source: Option<String>,
+    /// Unique identifier of the book, like an ISBN or URL [default: a UUID].
+    #[clap(long, value_name = "ID", display_order = 22, forbid_empty_values(true))]
+    pub identifier: Option<String>,
+    /// Name of the series the greentext is part of.
+    #[clap(
+        long,
+        value_name = "NAME",
+        display_order = 23,
+        forbid_empty_values(true)
+    )]
+    pub series: Option<String>,
+    /// Position of the greentext in its series, like `2` or `2.5`.
+    #[clap(
+        long,
+        value_name = "N",
+        display_order = 24,
+        validator(series_index_validator)
+    )]
+    pub series_index: Option<f64>,
+    /// Manifest to take the metadata of the book from, like a sidecar file kept along with the
+    /// chapters.
+    ///
+    /// Only its metadata is used, and metadata given as flags or in the manifest being built
+    /// takes precedence over it.
+    #[clap(
+        long,
+        value_name = "FILE",
+        display_order = 25,
+        value_hint(ValueHint::FilePath),
+        forbid_empty_values(true)
+    )]
+    pub metadata: Option<String>,
+    /// Path for the generated epub file.
+    #[clap(
+        short,
+        long,
+        value_name = "PATH",
+        display_order = 26,
+        value_hint(ValueHint::FilePath),
+        forbid_empty_values(true)
+    )]
+    pub output: Option<String>,
+    /// Look of the book [default: default].
+    ///
+    /// `--green-color` and `--spoiler-color` take precedence over the colors of the theme.
+    #[clap(long, arg_enum, value_name = "THEME", display_order = 27)]
+    pub theme: Option<Theme>,
+    /// RGB color of the green highlight in hexadecimal notation [default: the one of the theme,
+    /// #2CAF26 in the default one].
     #[clap(
         long,
-        default_value = "#2CAF26",
         value_name = "COLOR",
-        display_order = 6,
+        display_order = 28,
         forbid_empty_values(true),
         validator(hex_color_validator)
     )]
-    pub green_color: String,
-    /// RGB color of the spoiler highlight in hexadecimal notation.
+    pub green_color: Option<String>,
+    /// RGB color of the spoiler highlight in hexadecimal notation, the one of their outline in the
+    /// e-ink theme [default: the one of the theme, #000 in the default one].
     #[clap(
         long,
-        default_value = "#000",
         value_name = "COLOR",
-        display_order = 7,
+        display_order = 29,
         forbid_empty_values(true),
         validator(hex_color_validator)
     )]
-    pub spoiler_color: String,
+    pub spoiler_color: Option<String>,
+    /// Highlight lines starting with `<` with the given RGB color in hexadecimal notation.
+    #[clap(
+        long,
+        value_name = "COLOR",
+        display_order = 30,
+        forbid_empty_values(true),
+        validator(hex_color_validator)
+    )]
+    pub orange_color: Option<String>,
+    /// Highlight lines starting with `^` with the given RGB color in hexadecimal notation.
+    #[clap(
+        long,
+        value_name = "COLOR",
+        display_order = 31,
+        forbid_empty_values(true),
+        validator(hex_color_validator)
+    )]
+    pub caret_color: Option<String>,
+    /// Syntax recognized as a spoiler [default: bbcode].
+    ///
+    /// Can be used multiple times or given a comma separated list to recognize more than one.
+    #[clap(
+        long = "spoiler-syntax",
+        arg_enum,
+        value_name = "SYNTAX",
+        display_order = 32,
+        multiple_occurrences(true),
+        use_delimiter(true)
+    )]
+    pub spoiler_syntaxes: Vec<Spoiler>,
+    /// How spoilers are rendered [default: blackout].
+    ///
+    /// E-readers without support for popup footnotes show them at the end of the chapter.
+    #[clap(long, arg_enum, value_name = "MODE", display_order = 33)]
+    pub spoiler_mode: Option<SpoilerRendering>,
+    /// Stylesheet added after the generated rules of the book.
+    ///
+    /// Fonts and images it refers to with `url()` are packed in the book, looked for next to
+    /// it. Can be used multiple times to add more than one, in the order given.
+    #[clap(
+        long = "stylesheet",
+        value_name = "FILE",
+        display_order = 34,
+        multiple_occurrences(true),
+        forbid_empty_values(true),
+        value_hint(ValueHint::FilePath)
+    )]
+    pub stylesheets: Vec<String>,
+    /// Value of a CSS variable of the generated rules, like `post-spacing=2em`.
+    ///
+    /// Variables of the theme are listed in the README, variables used with `var()` in the
+    /// stylesheets given with `--stylesheet` can be set too. Can be used multiple times to set
+    /// more than one.
+    #[clap(
+        long = "css-var",
+        value_name = "NAME=VALUE",
+        display_order = 35,
+        multiple_occurrences(true),
+        parse(try_from_str = parse_css_var)
+    )]
+    pub css_vars: Vec<(String, String)>,
+    /// Title of the chapter with the given number, counting from 1.
+    ///
+    /// Can be used multiple times to set more than one.
+    #[clap(
+        long = "chapter-title",
+        value_name = "N=TITLE",
+        display_order = 36,
+        parse(try_from_str = parse_chapter_title)
+    )]
+    pub chapter_titles: Vec<(usize, String)>,
+    /// Regular expression matched against the file name of each chapter, its title is made from
+    /// the capture groups.
+    ///
+    /// Groups that matched are joined with spaces, the whole match is used when there aren't any.
+    /// Chapters with a file name that doesn't match are titled after their file stem.
+    #[clap(
+        long,
+        value_name = "REGEX",
+        display_order = 37,
+        forbid_empty_values(true)
+    )]
+    pub chapter_title_regex: Option<String>,
+    /// Take the title of each chapter from the first line of its file, which is left out of it.
+    #[clap(long, display_order = 38)]
+    pub chapter_title_first_line: bool,
+    /// Format of the input files [default: auto].
+    #[clap(long, arg_enum, value_name = "FORMAT", display_order = 39)]
+    pub input_format: Option<InputFormat>,
+    /// How posts by the OP are recognized in threads, the rest are left out [default: auto].
+    #[clap(long, arg_enum, value_name = "BY", display_order = 40)]
+    pub op_match: Option<OpMatch>,
+    /// Number of a post to keep from threads instead of the ones by the OP.
+    ///
+    /// Can be used multiple times or given a comma separated list to keep more than one.
+    #[clap(
+        long = "post",
+        value_name = "NUMBER",
+        display_order = 41,
+        multiple_occurrences(true),
+        use_delimiter(true)
+    )]
+    pub posts: Vec<u64>,
+    /// Leave out the header with the author, time and number at the start of each post.
+    #[clap(long, display_order = 42)]
+    pub no_post_headers: bool,
+    /// Title of the chapter read from standard input when `-` is given as a file [default:
+    /// stdin].
+    #[clap(
+        long,
+        value_name = "TITLE",
+        display_order = 43,
+        forbid_empty_values(true)
+    )]
+    pub stdin_title: Option<String>,
+    /// Order of the files found in directories and glob patterns given as files [default:
+    /// natural].
+    #[clap(long, arg_enum, value_name = "ORDER", display_order = 44)]
+    pub sort: Option<SortOrder>,
+    /// Generate the same bytes every time for the same inputs, with the modification date taken
+    /// from `SOURCE_DATE_EPOCH` or else 1980-01-01.
+    ///
+    /// Setting `SOURCE_DATE_EPOCH` turns this on too.
+    #[clap(long, display_order = 45)]
+    pub reproducible: bool,
+}
+
+#[derive(ClapArgs, Debug)]
+pub struct BuildArgs {
+    /// Manifest describing the book, in TOML or JSON format.
+    ///
+    /// Relative paths in it are taken from the directory it is in. Options given as flags take
+    /// precedence over the ones in it.
+    #[clap(
+        value_name = "MANIFEST",
+        value_hint(ValueHint::FilePath),
+        forbid_empty_values(true)
+    )]
+    pub manifest: String,
+    #[clap(flatten)]
+    pub book: BookArgs,
+}
+
+#[derive(ClapArgs, Debug)]
+pub struct AppendArgs {
+    /// EPUB built by green2epub to add the chapters to.
+    ///
+    /// It is rewritten in place unless `--output` is given, keeping its metadata, cover,
+    /// stylesheet and chapters. Metadata given as flags replaces the one in it.
+    #[clap(
+        value_name = "EPUB",
+        value_hint(ValueHint::FilePath),
+        forbid_empty_values(true)
+    )]
+    pub epub: String,
+    #[clap(flatten)]
+    pub book: BookArgs,
+    /// Text files in greentext format to add as chapters, after the ones already in the book.
+    #[clap(
+        value_name = "FILE",
+        value_hint(ValueHint::FilePath),
+        required(true),
+        forbid_empty_values(true)
+    )]
+    pub files: Vec<String>,
+}
+
+#[derive(ClapArgs, Debug)]
+pub struct ExtractArgs {
+    /// Directory to write the text files, cover and manifest to [default: name of the EPUB
+    /// without extension].
+    #[clap(
+        short,
+        long,
+        value_name = "DIR",
+        value_hint(ValueHint::DirPath),
+        forbid_empty_values(true)
+    )]
+    pub directory: Option<String>,
+    /// EPUB built by green2epub to extract the chapters of.
+    #[clap(
+        value_name = "EPUB",
+        value_hint(ValueHint::FilePath),
+        forbid_empty_values(true)
+    )]
+    pub epub: String,
+}
+
+#[derive(ClapArgs, Debug)]
+pub struct CheckArgs {
+    /// Format of the report.
+    #[clap(
+        long,
+        arg_enum,
+        value_name = "FORMAT",
+        default_value = "human",
+        display_order = 1
+    )]
+    pub format: ReportFormat,
+    /// Syntax recognized as a spoiler.
+    ///
+    /// Can be used multiple times or given a comma separated list to recognize more than one.
+    #[clap(
+        long = "spoiler-syntax",
+        arg_enum,
+        value_name = "SYNTAX",
+        default_value = "bbcode",
+        display_order = 2,
+        multiple_occurrences(true),
+        use_delimiter(true)
+    )]
+    pub spoiler_syntaxes: Vec<Spoiler>,
+    /// Text files in greentext format to check.
+    #[clap(
+        value_name = "FILE",
+        value_hint(ValueHint::FilePath),
+        required(true),
+        forbid_empty_values(true)
+    )]
+    pub files: Vec<String>,
+}
+
+#[derive(Subcommand, Debug)]
+#[allow(clippy::large_enum_variant)]
+pub enum Command {
+    /// Add chapters to an EPUB built by green2epub.
+    Append(AppendArgs),
+    /// Create an EPUB from a manifest.
+    Build(BuildArgs),
+    /// Report problems in text files in greentext format without creating an EPUB.
+    Check(CheckArgs),
+    /// Write the chapters of an EPUB built by green2epub back as text files, along with a
+    /// manifest to build it again.
+    Extract(ExtractArgs),
+}
+
+#[derive(Parser, Debug)]
+#[clap(
+    version,
+    author,
+    setting(AppSettings::SubcommandsNegateReqs),
+    setting(AppSettings::SubcommandPrecedenceOverArg),
+    mut_arg("title", |arg| arg.required(true)),
+    mut_arg("authors", |arg| arg.required(true)),
+    mut_arg("output", |arg| arg.required(true))
+)]
+/// Create an EPUB from text files in greentext format.
+pub struct Args {
+    #[clap(subcommand)]
+    pub command: Option<Command>,
+    #[clap(flatten)]
+    pub book: BookArgs,
     /// Shows verbose output, can be used multiple times to set level of verbosity.
-    #[clap(short, long, display_order = 8, parse(from_occurrences))]
+    #[clap(short, long, display_order = 46, global(true), parse(from_occurrences))]
     pub verbose: usize,
     /// Supress all output.
-    #[clap(short, long, display_order = 9, conflicts_with("verbose"))]
+    #[clap(
+        short,
+        long,
+        display_order = 47,
+        global(true),
+        conflicts_with("verbose")
+    )]
     pub quiet: bool,
     /// When to use colors.
     #[clap(
         long,
         arg_enum,
         default_value_t,
-        display_order = 10,
+        display_order = 48,
+        global(true),
         value_name = "WHEN"
     )]
     pub color: Color,
-    /// Path for the generated epub file.
-    #[clap(
-        short,
-        long,
-        value_name = "PATH",
-        display_order = 5,
-        value_hint(ValueHint::FilePath),
-        forbid_empty_values(true)
-    )]
-    pub output: String,
-    /// Text files in greentext format to convert.
+    /// Text files in greentext format to convert, `-` reads one from standard input.
+    ///
+    /// Directories are expanded to the files in them and glob patterns to the files they match.
     #[clap(
         value_name = "FILE",
         value_hint(ValueHint::FilePath),
@@ -127,3 +785,28 @@ pub struct Args {
     )]
     pub files: Vec<String>,
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn dates() {
+        for date in ["2021", "2021-03", "2021-03-31", "2020-02-29", "2000-02-29"] {
+            assert!(date_validator(date).is_ok(), "{:?} is valid", date);
+        }
+        for date in [
+            "21",
+            "2021-13",
+            "2021-00-10",
+            "2021-03-32",
+            "2021-02-29",
+            "2021-02-31",
+            "2021-04-31",
+            "1900-02-29",
+            "2021-3-1",
+        ] {
+            assert!(date_validator(date).is_err(), "{:?} is invalid", date);
+        }
+    }
+}
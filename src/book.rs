@@ -0,0 +1,968 @@
+use std::io::Write;
+use std::time::SystemTime;
+
+use epub_builder::{EpubBuilder, EpubContent, EpubVersion, ReferenceType, ZipLibrary};
+use imagesize::ImageType;
+
+use crate::check::Problem;
+use crate::content::{
+    coverpage_content, generated_coverpage_content, stylesheet_content, with_language,
+    PasteContent, Theme, COVER_STYLESHEET,
+};
+use crate::cover::{CoverColors, CoverLayout, GeneratedCover, GENERATED_COVER_FILE};
+use crate::errors::{CliError, CliResult, ResultExt};
+use crate::metadata::{validate_language, Contributor, Metadata, Role};
+use crate::package::repack;
+use crate::parser::{
+    Highlight, LineParser, MarkedLine, PostIndex, SpoilerMode, SpoilerSyntax, Token,
+};
+use crate::stylesheet::{resource_mime_type, UserStylesheet, RESOURCES_DIR};
+use crate::tag::Tag;
+
+pub const DEFAULT_GREEN_COLOR: &str = "#2CAF26";
+pub const DEFAULT_SPOILER_COLOR: &str = "#000";
+
+/// Name of the file of a chapter, relative to the directory where all content is stored.
+fn paste_file(count: usize) -> String {
+    format!("paste-{:03}.xhtml", count)
+}
+
+/// Post of a thread, with its body already split into lines of markup.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Post {
+    pub number: u64,
+    pub author: String,
+    pub tripcode: Option<String>,
+    pub time: Option<String>,
+    pub lines: Vec<MarkedLine>,
+}
+
+#[derive(Debug, Clone)]
+enum Content {
+    Text(String),
+    Posts(Vec<Post>),
+    /// Chapter of a book that was already built, kept as is.
+    Rendered {
+        number: usize,
+        xhtml: String,
+        /// Posts anchored in it, which other chapters can link to.
+        anchors: Vec<u64>,
+    },
+}
+
+/// A chapter of the book, made either from text in greentext format or from posts of a thread.
+#[derive(Debug, Clone)]
+pub struct Chapter {
+    title: String,
+    content: Content,
+    source: Option<String>,
+    /// Number of lines removed from the start of the text.
+    line_offset: usize,
+}
+
+impl Chapter {
+    pub fn new<T, S>(title: T, text: S) -> Self
+    where
+        T: ToString,
+        S: ToString,
+    {
+        Self {
+            title: title.to_string(),
+            content: Content::Text(text.to_string()),
+            source: None,
+            line_offset: 0,
+        }
+    }
+
+    /// Creates a chapter from posts, each one placed in its own block.
+    pub fn from_posts<T>(title: T, posts: Vec<Post>) -> Self
+    where
+        T: ToString,
+    {
+        Self {
+            title: title.to_string(),
+            content: Content::Posts(posts),
+            source: None,
+            line_offset: 0,
+        }
+    }
+
+    /// Creates a chapter from the content of the given `paste-NNN.xhtml` file of a book that
+    /// was already built.
+    pub(crate) fn rendered<T>(title: T, number: usize, xhtml: String, anchors: Vec<u64>) -> Self
+    where
+        T: ToString,
+    {
+        Self {
+            title: title.to_string(),
+            content: Content::Rendered {
+                number,
+                xhtml,
+                anchors,
+            },
+            source: None,
+            line_offset: 0,
+        }
+    }
+
+    pub fn with_title<T>(mut self, title: T) -> Self
+    where
+        T: ToString,
+    {
+        self.title = title.to_string();
+        self
+    }
+
+    /// Takes the title from the first line of the text, which is then removed from it.
+    ///
+    /// The title is kept as is if the first line is blank or the chapter isn't made from text.
+    pub fn with_title_from_first_line(mut self) -> Self {
+        let text = match &self.content {
+            Content::Text(text) => text,
+            _ => {
+                warn!(
+                    "Chapter not made from text doesn't have a first line, keeping its title: {:?}",
+                    &self.title
+                );
+                return self;
+            }
+        };
+
+        let (first_line, rest) = match text.split_once('\n') {
+            Some((first_line, rest)) => (first_line, rest),
+            None => (text.as_str(), ""),
+        };
+
+        let title = first_line.trim();
+        if title.is_empty() {
+            warn!(
+                "First line of chapter is blank, keeping its title: {:?}",
+                &self.title
+            );
+            return self;
+        }
+
+        self.title = title.into();
+        self.content = Content::Text(rest.into());
+        self.line_offset += 1;
+        self
+    }
+
+    /// Sets where the text comes from, like the path of its file, to point to positions in it
+    /// when warning about problems.
+    pub fn with_source<S>(mut self, source: S) -> Self
+    where
+        S: ToString,
+    {
+        self.source = Some(source.to_string());
+        self
+    }
+
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    /// Text of the chapter, if it is made from text.
+    pub fn text(&self) -> Option<&str> {
+        match &self.content {
+            Content::Text(text) => Some(text),
+            _ => None,
+        }
+    }
+
+    /// Posts of the chapter, which are none if it isn't made from posts.
+    pub fn posts(&self) -> &[Post] {
+        match &self.content {
+            Content::Posts(posts) => posts,
+            _ => &[],
+        }
+    }
+
+    /// Number of the `paste-NNN.xhtml` file the chapter had in the book it was built in.
+    fn number(&self) -> Option<usize> {
+        match &self.content {
+            Content::Rendered { number, .. } => Some(*number),
+            _ => None,
+        }
+    }
+
+    /// Where the text comes from, which is the title if it wasn't set.
+    pub fn source(&self) -> &str {
+        self.source.as_deref().unwrap_or(&self.title)
+    }
+
+    /// Tokens of the whole chapter, as they are parsed.
+    fn tokens(&self) -> Vec<Token> {
+        match &self.content {
+            Content::Text(text) => PostIndex::tokenize_text(text),
+            Content::Posts(posts) => posts
+                .iter()
+                .flat_map(|post| {
+                    std::iter::once(Token::PostNumber(post.number))
+                        .chain(post.lines.iter().flat_map(|line| line.tokens().to_vec()))
+                        .collect::<Vec<Token>>()
+                })
+                .collect(),
+            Content::Rendered { anchors, .. } => {
+                anchors.iter().copied().map(Token::PostNumber).collect()
+            }
+        }
+    }
+
+    /// Number of lines the chapter has to parse.
+    fn line_count(&self) -> usize {
+        match &self.content {
+            Content::Text(text) => text.lines().count(),
+            Content::Posts(posts) => posts.iter().map(|post| post.lines.len()).sum(),
+            Content::Rendered { .. } => 0,
+        }
+    }
+
+    fn to_paste(
+        &self,
+        posts: &PostIndex,
+        file: &str,
+        builder: &BookBuilder,
+        progress: &mut dyn FnMut(Progress),
+    ) -> PasteContent {
+        let mut paste = PasteContent::new(&self.title);
+        paste
+            .spoiler_mode(builder.spoiler_mode)
+            .post_headers(builder.post_headers)
+            .language(builder.metadata.language());
+
+        let mut line_parser = LineParser::with_posts(posts, file);
+        line_parser
+            .highlights(builder.highlights.clone())
+            .spoiler_mode(builder.spoiler_mode);
+        if !builder.spoilers.is_empty() {
+            line_parser.spoilers(builder.spoilers.clone());
+        }
+
+        for _ in 0..self.line_offset {
+            line_parser.skip_line();
+        }
+
+        match &self.content {
+            Content::Text(text) => {
+                for line in text.lines() {
+                    progress(Progress::Line);
+                    if line.is_empty() {
+                        paste.add_line(Tag::new("br"));
+                        line_parser.skip_line();
+                        continue;
+                    }
+
+                    paste.add_line(line_parser.parse(line));
+                }
+            }
+            Content::Posts(posts) => {
+                for post in posts {
+                    line_parser.anchor_post(post.number);
+
+                    let lines: Vec<Tag> = post
+                        .lines
+                        .iter()
+                        .map(|line| {
+                            progress(Progress::Line);
+                            if line.is_empty() {
+                                line_parser.skip_line();
+                                Tag::new("br")
+                            } else {
+                                line_parser.parse_marked(line)
+                            }
+                        })
+                        .collect();
+                    paste.add_post(post, lines);
+                }
+            }
+            Content::Rendered { .. } => unreachable!("rendered chapters aren't parsed"),
+        };
+
+        for diagnostic in line_parser.unclosed() {
+            let what = match diagnostic.problem {
+                Problem::UnclosedSpoiler => "Spoiler",
+                _ => "Formatting",
+            };
+            warn!(
+                "{} opened at {}:{} never closed, it extends to the end of the chapter",
+                what,
+                self.source(),
+                diagnostic.position
+            );
+        }
+
+        for note in line_parser.take_notes() {
+            paste.add_note(note);
+        }
+
+        paste
+    }
+}
+
+/// Cover image of the book, with its format and dimensions already recognized.
+#[derive(Debug, Clone)]
+pub struct Cover {
+    bytes: Vec<u8>,
+    extension: &'static str,
+    mime_type: &'static str,
+    dimensions: (usize, usize),
+}
+
+impl Cover {
+    /// Recognizes the format and dimensions of an image, which can be either BMP, GIF, JPEG, PNG
+    /// or WebP.
+    pub fn new(bytes: Vec<u8>) -> CliResult<Self> {
+        let img_type =
+            imagesize::image_type(&bytes).context("failed to recognize cover image format")?;
+        let img_size =
+            imagesize::blob_size(&bytes).context("failed to get cover image dimensions")?;
+
+        let (extension, mime_type) = match img_type {
+            ImageType::Bmp => ("bmp", "image/bmp"),
+            ImageType::Gif => ("gif", "image/gif"),
+            ImageType::Jpeg => ("jpg", "image/jpeg"),
+            ImageType::Png => ("png", "image/png"),
+            ImageType::Webp => ("webp", "image/webp"),
+            _ => {
+                return Err(CliError::from(format!(
+                    "invalid format for cover image: {:?}",
+                    img_type
+                )))
+            }
+        };
+        let dimensions = (img_size.width, img_size.height);
+
+        debug!("Cover image format: {:?}", extension);
+        debug!("Cover image size: {:?}", dimensions);
+
+        Ok(Self {
+            bytes,
+            extension,
+            mime_type,
+            dimensions,
+        })
+    }
+
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    pub fn extension(&self) -> &str {
+        self.extension
+    }
+
+    pub fn mime_type(&self) -> &str {
+        self.mime_type
+    }
+
+    pub fn dimensions(&self) -> (usize, usize) {
+        self.dimensions
+    }
+}
+
+/// Builder of a [`Book`], holds its metadata and chapters until it is built.
+#[derive(Debug, Clone)]
+pub struct BookBuilder {
+    metadata: Metadata,
+    cover: Option<Cover>,
+    generated_cover: Option<(CoverLayout, CoverColors)>,
+    theme: Theme,
+    green_color: Option<String>,
+    spoiler_color: Option<String>,
+    highlights: Vec<Highlight>,
+    spoilers: Vec<SpoilerSyntax>,
+    spoiler_mode: SpoilerMode,
+    post_headers: bool,
+    stylesheet: Option<Vec<u8>>,
+    user_stylesheets: Vec<UserStylesheet>,
+    css_variables: Vec<(String, String)>,
+    resources: Vec<(String, Vec<u8>, String)>,
+    modified: Option<SystemTime>,
+    chapters: Vec<Chapter>,
+}
+
+impl BookBuilder {
+    pub fn new<S>(title: S) -> Self
+    where
+        S: ToString,
+    {
+        Self {
+            metadata: Metadata {
+                title: title.to_string(),
+                ..Metadata::default()
+            },
+            cover: None,
+            generated_cover: None,
+            theme: Theme::default(),
+            green_color: None,
+            spoiler_color: None,
+            highlights: Vec::new(),
+            spoilers: Vec::new(),
+            spoiler_mode: SpoilerMode::default(),
+            post_headers: true,
+            stylesheet: None,
+            user_stylesheets: Vec::new(),
+            css_variables: Vec::new(),
+            resources: Vec::new(),
+            modified: None,
+            chapters: Vec::new(),
+        }
+    }
+
+    /// Adds an author, can be called multiple times to add more than one.
+    pub fn author<S>(&mut self, author: S) -> &mut Self
+    where
+        S: ToString,
+    {
+        self.metadata
+            .contributors
+            .push(Contributor::new(author, Role::Author));
+        self
+    }
+
+    /// Adds an author, editor, translator or illustrator, can be called multiple times to add
+    /// more than one.
+    pub fn contributor(&mut self, contributor: Contributor) -> &mut Self {
+        self.metadata.contributors.push(contributor);
+        self
+    }
+
+    /// Adds a subject, can be called multiple times to add more than one.
+    pub fn subject<S>(&mut self, subject: S) -> &mut Self
+    where
+        S: ToString,
+    {
+        self.metadata.subjects.push(subject.to_string());
+        self
+    }
+
+    pub fn description<S>(&mut self, description: S) -> &mut Self
+    where
+        S: ToString,
+    {
+        self.metadata.description = Some(description.to_string());
+        self
+    }
+
+    pub fn publisher<S>(&mut self, publisher: S) -> &mut Self
+    where
+        S: ToString,
+    {
+        self.metadata.publisher = Some(publisher.to_string());
+        self
+    }
+
+    /// Language of the text as a BCP 47 tag, like `en` or `es-419`.
+    pub fn language<S>(&mut self, language: S) -> &mut Self
+    where
+        S: ToString,
+    {
+        self.metadata.language = Some(language.to_string());
+        self
+    }
+
+    /// Date of publication in W3CDTF format, like `2021`, `2021-03` or `2021-03-04`.
+    pub fn date<S>(&mut self, date: S) -> &mut Self
+    where
+        S: ToString,
+    {
+        self.metadata.date = Some(date.to_string());
+        self
+    }
+
+    pub fn rights<S>(&mut self, rights: S) -> &mut Self
+    where
+        S: ToString,
+    {
+        self.metadata.rights = Some(rights.to_string());
+        self
+    }
+
+    /// Where the book comes from, like the URL of the thread.
+    pub fn source<S>(&mut self, source: S) -> &mut Self
+    where
+        S: ToString,
+    {
+        self.metadata.source = Some(source.to_string());
+        self
+    }
+
+    /// Unique identifier of the book, like an ISBN or URL, instead of a made up UUID.
+    pub fn identifier<S>(&mut self, identifier: S) -> &mut Self
+    where
+        S: ToString,
+    {
+        self.metadata.identifier = Some(identifier.to_string());
+        self
+    }
+
+    /// Series the book belongs to.
+    pub fn series<S>(&mut self, series: S) -> &mut Self
+    where
+        S: ToString,
+    {
+        self.metadata.series = Some(series.to_string());
+        self
+    }
+
+    /// Position of the book in its series, only written if the series is set.
+    pub fn series_index(&mut self, index: f64) -> &mut Self {
+        self.metadata.series_index = Some(index);
+        self
+    }
+
+    pub fn cover(&mut self, cover: Cover) -> &mut Self {
+        self.cover = Some(cover);
+        self
+    }
+
+    /// Generate a cover from the title, authors and series when no cover image is set.
+    pub fn generate_cover(&mut self, layout: CoverLayout, colors: CoverColors) -> &mut Self {
+        self.generated_cover = Some((layout, colors));
+        self
+    }
+
+    /// Look of the book, the green and spoiler colors take precedence over the ones of the
+    /// theme.
+    pub fn theme(&mut self, theme: Theme) -> &mut Self {
+        self.theme = theme;
+        self
+    }
+
+    /// RGB color of the green highlight in hexadecimal notation [default: the one of the theme].
+    pub fn green_color<S>(&mut self, color: S) -> &mut Self
+    where
+        S: ToString,
+    {
+        self.green_color = Some(color.to_string());
+        self
+    }
+
+    /// RGB color of the spoiler highlight in hexadecimal notation [default: the one of the
+    /// theme].
+    pub fn spoiler_color<S>(&mut self, color: S) -> &mut Self
+    where
+        S: ToString,
+    {
+        self.spoiler_color = Some(color.to_string());
+        self
+    }
+
+    /// Adds a highlight for lines with a prefix other than greentext's `>`, can be called
+    /// multiple times to add more than one.
+    pub fn highlight(&mut self, highlight: Highlight) -> &mut Self {
+        self.highlights.push(highlight);
+        self
+    }
+
+    /// Adds a syntax to recognize as a spoiler, can be called multiple times to add more than
+    /// one.
+    ///
+    /// The default `[spoiler]` syntax is only recognized when none is added.
+    pub fn spoiler_syntax(&mut self, syntax: SpoilerSyntax) -> &mut Self {
+        self.spoilers.push(syntax);
+        self
+    }
+
+    pub fn spoiler_mode(&mut self, mode: SpoilerMode) -> &mut Self {
+        self.spoiler_mode = mode;
+        self
+    }
+
+    /// Whether posts of chapters made from threads start with a header showing their author,
+    /// time and number, which is the default.
+    pub fn post_headers(&mut self, post_headers: bool) -> &mut Self {
+        self.post_headers = post_headers;
+        self
+    }
+
+    /// Uses the given stylesheet instead of generating one from the colors and spoiler mode,
+    /// like the one of a book that chapters are appended to.
+    pub fn stylesheet(&mut self, stylesheet: Vec<u8>) -> &mut Self {
+        self.stylesheet = Some(stylesheet);
+        self
+    }
+
+    /// Adds a stylesheet after the generated rules, can be called multiple times to add more
+    /// than one.
+    pub fn user_stylesheet(&mut self, stylesheet: UserStylesheet) -> &mut Self {
+        self.user_stylesheets.push(stylesheet);
+        self
+    }
+
+    /// Overrides the value of a variable of the theme, or sets one used by the stylesheets
+    /// added, which `var(--name)` is replaced with.
+    pub fn css_variable<N, V>(&mut self, name: N, value: V) -> &mut Self
+    where
+        N: ToString,
+        V: ToString,
+    {
+        self.css_variables
+            .push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Adds a file referred to by the stylesheet, like the ones of a book that chapters are
+    /// appended to, with its path relative to the stylesheet.
+    pub fn resource<H, M>(&mut self, href: H, bytes: Vec<u8>, mime_type: M) -> &mut Self
+    where
+        H: ToString,
+        M: ToString,
+    {
+        self.resources
+            .push((href.to_string(), bytes, mime_type.to_string()));
+        self
+    }
+
+    /// Makes the same book always generate the same bytes, by giving it this modification
+    /// date, an identifier derived from its content unless one is set and the same time for all
+    /// of its files.
+    pub fn reproducible(&mut self, modified: SystemTime) -> &mut Self {
+        self.modified = Some(modified);
+        self
+    }
+
+    /// Appends a chapter, they are placed in the book in the same order they are added.
+    pub fn chapter(&mut self, chapter: Chapter) -> &mut Self {
+        self.chapters.push(chapter);
+        self
+    }
+
+    pub fn build(&self) -> CliResult<Book> {
+        self.build_with_progress(|_| {})
+    }
+
+    /// Same as `build`, reporting the progress of parsing each chapter to the given callback.
+    pub fn build_with_progress<F>(&self, mut progress: F) -> CliResult<Book>
+    where
+        F: FnMut(Progress),
+    {
+        if self.chapters.is_empty() {
+            return Err(CliError::from(String::from(
+                "a book needs at least one chapter",
+            )));
+        }
+
+        if let Some(language) = &self.metadata.language {
+            validate_language(language)?;
+        }
+
+        // Colors given take precedence over the variables, which do over the theme.
+        let mut variables = self.css_variables.clone();
+        for (name, color) in [
+            ("green-color", &self.green_color),
+            ("spoiler-color", &self.spoiler_color),
+        ] {
+            if let Some(color) = color {
+                variables.push((name.into(), color.clone()));
+            }
+        }
+        let green_color = variables
+            .iter()
+            .rev()
+            .find(|(name, _)| name == "green-color")
+            .map_or(self.theme.green_color(), |(_, color)| color.as_str());
+
+        let mut resources: Vec<(String, &[u8], &str)> = self
+            .resources
+            .iter()
+            .map(|(href, bytes, mime_type)| (href.clone(), bytes.as_slice(), mime_type.as_str()))
+            .collect();
+        let stylesheet = match &self.stylesheet {
+            Some(stylesheet) => stylesheet.clone(),
+            None => {
+                let mut count = resources.len();
+                let mut extra = Vec::new();
+                for stylesheet in &self.user_stylesheets {
+                    let (css, files) = stylesheet.pack(|name| {
+                        count += 1;
+                        format!("{}/{:02}-{}", RESOURCES_DIR, count, name)
+                    })?;
+                    for (href, bytes) in files {
+                        let mime_type = resource_mime_type(&href).unwrap_or_default();
+                        resources.push((href, bytes, mime_type));
+                    }
+                    extra.push(css);
+                }
+
+                stylesheet_content(
+                    self.spoiler_mode,
+                    &self.highlights,
+                    self.theme,
+                    &variables,
+                    &extra,
+                )?
+            }
+        };
+
+        let mut epub = EpubBuilder::new(ZipLibrary::new()?)?;
+        epub.epub_version(EpubVersion::V30)
+            .metadata("title", &self.metadata.title)?
+            .stylesheet(stylesheet.as_slice())?;
+        for (href, bytes, mime_type) in resources {
+            epub.add_resource(href, bytes, mime_type)?;
+        }
+
+        let coverpage = match (&self.cover, self.generated_cover) {
+            (Some(cover), _) => {
+                let href = format!("img/cover.{}", cover.extension);
+
+                debug!("Adding cover resources to EPUB");
+                epub.add_cover_image(&href, cover.bytes.as_slice(), cover.mime_type)?;
+                Some(coverpage_content(
+                    &href,
+                    cover.dimensions,
+                    self.metadata.language(),
+                ))
+            }
+            (None, Some((layout, colors))) => {
+                let cover = GeneratedCover::new(&self.metadata, layout, colors, green_color);
+
+                debug!("Adding generated cover resources to EPUB");
+                epub.add_cover_image(
+                    GENERATED_COVER_FILE,
+                    cover.to_png()?.as_slice(),
+                    "image/png",
+                )?;
+                Some(generated_coverpage_content(
+                    &cover,
+                    self.metadata.language(),
+                ))
+            }
+            (None, None) => None,
+        };
+
+        if let Some(coverpage) = &coverpage {
+            epub.add_resource(
+                COVER_STYLESHEET,
+                include_bytes!(concat!(
+                    env!("CARGO_MANIFEST_DIR"),
+                    "/static/coverstyle.css"
+                ))
+                .as_slice(),
+                "text/css",
+            )?;
+            epub.add_content(
+                EpubContent::new("content/cover.xhtml", coverpage.as_bytes())
+                    .title("Cover")
+                    .reftype(ReferenceType::Cover),
+            )?;
+        }
+
+        // NOTE: Keep TOC after the cover page.
+        epub.inline_toc();
+
+        // Chapters of a book that was already built keep their file, the rest are numbered after
+        // the last one.
+        let mut next = 1;
+        let files: Vec<String> = self
+            .chapters
+            .iter()
+            .map(|chapter| {
+                let number = chapter.number().unwrap_or(next);
+                next = number + 1;
+                paste_file(number)
+            })
+            .collect();
+        let posts =
+            PostIndex::from_tokens(files.iter().zip(self.chapters.iter().map(Chapter::tokens)));
+
+        for (file, chapter) in files.iter().zip(&self.chapters) {
+            let xhtml = match &chapter.content {
+                Content::Rendered { xhtml, .. } => with_language(xhtml, self.metadata.language()),
+                _ => {
+                    progress(Progress::Chapter {
+                        source: chapter.source(),
+                        lines: chapter.line_count(),
+                    });
+                    let paste = chapter.to_paste(&posts, file, self, &mut progress);
+                    progress(Progress::Parsed);
+                    info!("Parsed {:?}", &chapter.title);
+                    paste.build()
+                }
+            };
+
+            debug!("Adding chapter {:?} to EPUB", &chapter.title);
+            epub.add_content(
+                EpubContent::new(format!("content/{}", file), xhtml.as_bytes())
+                    .title(&chapter.title),
+            )?;
+        }
+
+        Ok(Book {
+            epub,
+            metadata: self.metadata.clone(),
+            cover: coverpage.is_some(),
+            modified: self.modified,
+        })
+    }
+}
+
+/// Progress of parsing the chapters of a book while it's built.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Progress<'a> {
+    /// A chapter with the given number of lines starts to be parsed.
+    Chapter { source: &'a str, lines: usize },
+    /// A line of the chapter was parsed.
+    Line,
+    /// The chapter was parsed.
+    Parsed,
+}
+
+/// An EPUB ready to be written.
+pub struct Book {
+    epub: EpubBuilder<ZipLibrary>,
+    metadata: Metadata,
+    cover: bool,
+    modified: Option<SystemTime>,
+}
+
+impl Book {
+    pub fn builder<S>(title: S) -> BookBuilder
+    where
+        S: ToString,
+    {
+        BookBuilder::new(title)
+    }
+
+    pub fn generate<W>(&mut self, mut writer: W) -> CliResult<()>
+    where
+        W: Write,
+    {
+        let mut bytes: Vec<u8> = Vec::new();
+        self.epub
+            .generate(&mut bytes)
+            .context("failed to generate EPUB")?;
+
+        let epub = repack(&bytes, &self.metadata, self.cover, self.modified)?;
+        writer.write_all(&epub).context("failed to write EPUB")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn generate_into_memory() {
+        let mut book = BookBuilder::new("Paste")
+            .author("Author")
+            .chapter(Chapter::new(
+                "001",
+                ">be me\n\n[spoiler]>be spoiled[/spoiler]",
+            ))
+            .build()
+            .unwrap();
+
+        let mut bytes: Vec<u8> = Vec::new();
+        book.generate(&mut bytes).unwrap();
+
+        assert!(bytes.starts_with(b"PK"), "expected a zip archive");
+    }
+
+    #[test]
+    fn post_index_across_chapters() {
+        let chapters = [
+            ("paste-001.xhtml", "Anonymous No.100\n>be me"),
+            (
+                "paste-002.xhtml",
+                "Anonymous No.200\n>>100\nAnonymous No.300",
+            ),
+        ];
+        let posts = PostIndex::new(chapters.iter().map(|(file, text)| (file, *text)));
+
+        assert_eq!(posts.href(100).as_deref(), Some("paste-001.xhtml#p100"));
+        // Found but never referenced.
+        assert_eq!(posts.href(200), None);
+        assert_eq!(posts.href(300), None);
+    }
+
+    #[test]
+    fn chapter_from_posts() {
+        let posts = vec![
+            Post {
+                number: 100,
+                author: "Anonymous".into(),
+                tripcode: Some("!Trip".into()),
+                lines: vec![MarkedLine::new()
+                    .text(">be ")
+                    .open_spoiler()
+                    .text("[b]me")
+                    .close_spoiler()
+                    .clone()],
+                ..Post::default()
+            },
+            Post {
+                number: 101,
+                author: "Anonymous".into(),
+                lines: vec![MarkedLine::new().post_ref(100).clone()],
+                ..Post::default()
+            },
+        ];
+        let chapter = Chapter::from_posts("Thread", posts);
+        let index = PostIndex::from_tokens([("paste-001.xhtml", chapter.tokens())]);
+        let html = chapter
+            .to_paste(
+                &index,
+                "paste-001.xhtml",
+                &BookBuilder::new("Paste"),
+                &mut |_| {},
+            )
+            .build();
+
+        assert!(html.contains(concat!(
+            r#"<article class="post" id="p100"><header class="post-header">"#,
+            r#"<span class="post-author">Anonymous</span> "#,
+            r#"<span class="post-tripcode">!Trip</span> "#,
+            r#"<span class="post-number">No.100</span></header>"#,
+            "<p>&gt;be <span>[b]me</span></p></article>"
+        )));
+        assert!(html.contains(r#"<a class="quotelink" href="paste-001.xhtml#p100">"#));
+
+        let mut builder = BookBuilder::new("Paste");
+        builder.post_headers(false);
+        let html = chapter
+            .to_paste(&index, "paste-001.xhtml", &builder, &mut |_| {})
+            .build();
+        assert!(html.contains(r#"<article class="post" id="p101"><p class="icolor"><a"#));
+        assert!(!html.contains("post-header"));
+    }
+
+    #[test]
+    fn title_from_first_line() {
+        let chapter = Chapter::new("001", "  Part One \n>be me\n").with_title_from_first_line();
+        assert_eq!(chapter.title(), "Part One");
+        assert_eq!(chapter.text(), Some(">be me\n"));
+
+        let chapter = Chapter::new("001", "\n>be me").with_title_from_first_line();
+        assert_eq!(chapter.title(), "001");
+        assert_eq!(chapter.text(), Some("\n>be me"));
+    }
+
+    #[test]
+    fn build_without_chapters() {
+        assert!(BookBuilder::new("Paste").author("Author").build().is_err());
+    }
+
+    #[test]
+    fn build_progress() {
+        let mut events = Vec::new();
+        BookBuilder::new("Paste")
+            .author("Author")
+            .chapter(Chapter::new("001", ">be me\n\nnormal line"))
+            .build_with_progress(|progress| events.push(format!("{:?}", progress)))
+            .unwrap();
+
+        assert_eq!(
+            events,
+            [
+                r#"Chapter { source: "001", lines: 3 }"#,
+                "Line",
+                "Line",
+                "Line",
+                "Parsed"
+            ]
+        );
+    }
+}
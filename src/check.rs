@@ -0,0 +1,209 @@
+use std::fmt;
+
+use crate::parser::{LineParser, SpoilerSyntax};
+
+/// Position in a text, where both line and column start at 1 and the column counts characters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Position {
+    pub fn new(line: usize, column: usize) -> Self {
+        Self { line, column }
+    }
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Problem {
+    /// Spoiler that extends to the end of the text, found where it is opened.
+    UnclosedSpoiler,
+    /// Formatting that extends to the end of the text, found where it is opened.
+    UnclosedFormatting,
+    /// Closing delimiter of a spoiler without an open one.
+    StraySpoilerClose,
+    /// Closing delimiter of a formatting without an open one.
+    StrayFormattingClose,
+    /// Spoiler without any text, found where it is opened.
+    EmptySpoiler,
+    /// Line ending different from the one of the first line, found at the end of the line.
+    MixedLineEndings,
+    /// Bytes that aren't valid UTF-8.
+    InvalidUtf8,
+}
+
+impl Problem {
+    /// Short identifier of the problem.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Problem::UnclosedSpoiler => "unclosed-spoiler",
+            Problem::UnclosedFormatting => "unclosed-formatting",
+            Problem::StraySpoilerClose => "stray-spoiler-close",
+            Problem::StrayFormattingClose => "stray-formatting-close",
+            Problem::EmptySpoiler => "empty-spoiler",
+            Problem::MixedLineEndings => "mixed-line-endings",
+            Problem::InvalidUtf8 => "invalid-utf8",
+        }
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self {
+            Problem::UnclosedSpoiler => "spoiler is never closed",
+            Problem::UnclosedFormatting => "formatting is never closed",
+            Problem::StraySpoilerClose => "closing spoiler tag without an opening one",
+            Problem::StrayFormattingClose => "closing formatting tag without an opening one",
+            Problem::EmptySpoiler => "spoiler is empty",
+            Problem::MixedLineEndings => "line ending differs from the one of the first line",
+            Problem::InvalidUtf8 => "invalid UTF-8 sequence",
+        };
+
+        write!(f, "{}", message)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub position: Position,
+    pub problem: Problem,
+}
+
+impl Diagnostic {
+    pub fn new(position: Position, problem: Problem) -> Self {
+        Self { position, problem }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.position, self.problem)
+    }
+}
+
+/// Position right after the given text, when it starts at `start`.
+fn position_after(start: Position, text: &str) -> Position {
+    text.chars().fold(start, |position, c| match c {
+        '\n' => Position::new(position.line + 1, 1),
+        _ => Position::new(position.line, position.column + 1),
+    })
+}
+
+/// Decodes the bytes as UTF-8, replacing invalid sequences with `U+FFFD` and reporting them.
+fn decode(bytes: &[u8], diagnostics: &mut Vec<Diagnostic>) -> String {
+    let mut text = String::with_capacity(bytes.len());
+    let mut position = Position::new(1, 1);
+
+    for chunk in bytes.utf8_chunks() {
+        text.push_str(chunk.valid());
+        position = position_after(position, chunk.valid());
+
+        if !chunk.invalid().is_empty() {
+            text.push(char::REPLACEMENT_CHARACTER);
+            diagnostics.push(Diagnostic::new(position, Problem::InvalidUtf8));
+            position.column += 1;
+        }
+    }
+
+    text
+}
+
+/// Reports lines that don't end the same way as the first one.
+fn check_line_endings(text: &str, diagnostics: &mut Vec<Diagnostic>) {
+    let mut first_ending: Option<&str> = None;
+
+    for (idx, line) in text.split_inclusive('\n').enumerate() {
+        let (content, ending) = match line.strip_suffix("\r\n") {
+            Some(content) => (content, "\r\n"),
+            None => match line.strip_suffix('\n') {
+                Some(content) => (content, "\n"),
+                None => continue,
+            },
+        };
+
+        match first_ending {
+            None => first_ending = Some(ending),
+            Some(first) if first != ending => diagnostics.push(Diagnostic::new(
+                Position::new(idx + 1, content.chars().count() + 1),
+                Problem::MixedLineEndings,
+            )),
+            _ => {}
+        };
+    }
+}
+
+/// Checks text in greentext format for problems, without building anything from it.
+pub fn check(bytes: &[u8], spoilers: &[SpoilerSyntax]) -> Vec<Diagnostic> {
+    let mut diagnostics: Vec<Diagnostic> = Vec::new();
+
+    let text = decode(bytes, &mut diagnostics);
+    check_line_endings(&text, &mut diagnostics);
+
+    let mut line_parser = LineParser::default();
+    if !spoilers.is_empty() {
+        line_parser.spoilers(spoilers.to_vec());
+    }
+
+    for line in text.lines() {
+        line_parser.parse(line);
+    }
+
+    diagnostics.extend(line_parser.take_diagnostics());
+    diagnostics.extend(line_parser.unclosed());
+    diagnostics.sort_by_key(|diagnostic| diagnostic.position);
+
+    diagnostics
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    macro_rules! assert_check {
+        ($raw:expr, [$(($line:expr, $column:expr, $problem:ident)),*]) => {
+            assert_eq!(
+                check($raw, &[]),
+                vec![$( Diagnostic::new(Position::new($line, $column), Problem::$problem) ),*],
+                "{:?}",
+                $raw
+            );
+        };
+    }
+
+    #[test]
+    fn no_problems() {
+        assert_check!(b">be me\n[spoiler]spoiler[/spoiler]\n\n>[b]bold[/b]\n", []);
+    }
+
+    #[test]
+    fn spoiler_problems() {
+        assert_check!(b"Line\n>be [spoiler]me\n", [(2, 5, UnclosedSpoiler)]);
+        assert_check!(b"Line\nno [/spoiler]\n", [(2, 4, StraySpoilerClose)]);
+        assert_check!(b"[spoiler]\n[/spoiler]", [(1, 1, EmptySpoiler)]);
+        assert_check!(
+            b"[b]unclosed [/i]\n",
+            [(1, 1, UnclosedFormatting), (1, 13, StrayFormattingClose)]
+        );
+    }
+
+    #[test]
+    fn line_ending_problems() {
+        assert_check!(b"Windows\r\nUnix\nWindows\r\n", [(2, 5, MixedLineEndings)]);
+        assert_check!(b"Unix\nUnix\n", []);
+    }
+
+    #[test]
+    fn encoding_problems() {
+        assert_check!(
+            b"Valid\nnot \xff valid \xc3\n",
+            [(2, 5, InvalidUtf8), (2, 13, InvalidUtf8)]
+        );
+    }
+}
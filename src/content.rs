@@ -1,4 +1,12 @@
-use crate::parser::RESET_FOREGROUND_CLASS;
+use regex::Regex;
+
+use crate::book::{Post, DEFAULT_GREEN_COLOR, DEFAULT_SPOILER_COLOR};
+use crate::cover::{GeneratedCover, COVER_HEIGHT, COVER_WIDTH};
+use crate::errors::{CliError, CliResult};
+use crate::metadata::{is_rtl_language, DEFAULT_LANGUAGE};
+use crate::parser::{
+    post_anchor, Highlight, SpoilerMode, POST_NUMBER_PREFIX, RESET_FOREGROUND_CLASS,
+};
 use crate::tag::{Child, Tag};
 
 const NS_XHTML: &str = "http://www.w3.org/1999/xhtml";
@@ -7,6 +15,40 @@ const NS_SVG: &str = "http://www.w3.org/2000/svg";
 const NS_XLINK: &str = "http://www.w3.org/1999/xlink";
 
 pub const COVER_STYLESHEET: &str = "style/coverstyle.css";
+/// Start of the comment naming the theme of a stylesheet, so it can be told when read back.
+pub(crate) const THEME_MARKER: &str = "/* theme: ";
+
+/// Sets the language of a document on its root element, along with its direction if it is
+/// written from right to left.
+fn language_attributes(html: &mut Tag, language: &str) {
+    html.attribute("lang", language)
+        .attribute("xml:lang", language);
+    if is_rtl_language(language) {
+        html.attribute("dir", "rtl");
+    }
+}
+
+/// Replaces the language and direction set on the root element of a document that was already
+/// generated, the language being a valid tag that doesn't need escaping.
+pub(crate) fn with_language(xhtml: &str, language: &str) -> String {
+    let root = Regex::new(r"<html\b[^>]*>").unwrap();
+    let attributes = Regex::new(r#"\s(?:lang|xml:lang|dir)="[^"]*""#).unwrap();
+
+    root.replace(xhtml, |captures: &regex::Captures| {
+        let tag = attributes.replace_all(&captures[0], "");
+        let (start, end) = tag.split_at(tag.len() - 1);
+        let direction = if is_rtl_language(language) {
+            r#" dir="rtl""#
+        } else {
+            ""
+        };
+        format!(
+            r#"{} lang="{}" xml:lang="{}"{}{}"#,
+            start, language, language, direction, end
+        )
+    })
+    .into_owned()
+}
 
 fn xhtml_content_from_html_tag(html: Tag) -> String {
     format!(
@@ -19,85 +61,312 @@ fn xhtml_content_from_html_tag(html: Tag) -> String {
 // remarkable differences.
 //  https://github.com/daniel-j/fimfic2epub/blob/master/src/templates.js#L353
 //  MIT License: https://github.com/daniel-j/fimfic2epub/blob/master/LICENSE
-pub fn coverpage_content<S>(href: S, (width, height): (usize, usize)) -> String
+fn coverpage<C>(drawing: C, (width, height): (usize, usize), language: &str) -> String
 where
-    S: AsRef<str>,
+    C: Into<Child>,
 {
     let mut html = Tag::new("html");
     html.attribute("xmlns", NS_XHTML)
-        .attribute("xmlns:epub", NS_OPS)
-        .attribute("lang", "en")
-        .attribute("xml:lang", "en")
-        .child(
-            Tag::new("head")
-                .child(Tag::new("meta").attribute("charset", "utf-8"))
-                .child(
-                    Tag::new("meta")
-                        .attribute("name", "viewport")
-                        .attribute("content", format!("width={}, height={}", width, height)),
-                )
-                .child(Tag::new("title").child("Cover"))
-                .child(
-                    Tag::new("link")
-                        .attribute("rel", "stylesheet")
-                        .attribute("type", "text/css")
-                        .attribute("href", format!("../{}", COVER_STYLESHEET)),
-                ),
-        )
-        .child(
-            Tag::new("body")
-                .attribute("epub:type", "frontmatter cover")
-                .attribute("id", "coverpage")
-                .child(
-                    Tag::new("svg")
-                        .attribute("xmlns", NS_SVG)
-                        .attribute("xmlns:xlink", NS_XLINK)
-                        .attribute("version", "1.1")
-                        .attribute("viewBox", format!("0 0 {} {}", width, height))
-                        .attribute("id", "cover")
-                        .child(
-                            Tag::new("image")
-                                .attribute("width", width)
-                                .attribute("height", height)
-                                .attribute("xlink:href", format!("../{}", href.as_ref())),
-                        ),
-                ),
-        );
+        .attribute("xmlns:epub", NS_OPS);
+    language_attributes(&mut html, language);
+    html.child(
+        Tag::new("head")
+            .child(Tag::new("meta").attribute("charset", "utf-8"))
+            .child(
+                Tag::new("meta")
+                    .attribute("name", "viewport")
+                    .attribute("content", format!("width={}, height={}", width, height)),
+            )
+            .child(Tag::new("title").child("Cover"))
+            .child(
+                Tag::new("link")
+                    .attribute("rel", "stylesheet")
+                    .attribute("type", "text/css")
+                    .attribute("href", format!("../{}", COVER_STYLESHEET)),
+            ),
+    )
+    .child(
+        Tag::new("body")
+            .attribute("epub:type", "frontmatter cover")
+            .attribute("id", "coverpage")
+            .child(
+                Tag::new("svg")
+                    .attribute("xmlns", NS_SVG)
+                    .attribute("xmlns:xlink", NS_XLINK)
+                    .attribute("version", "1.1")
+                    .attribute("viewBox", format!("0 0 {} {}", width, height))
+                    .attribute("id", "cover")
+                    .child(drawing),
+            ),
+    );
 
     xhtml_content_from_html_tag(html)
 }
 
-pub fn stylesheet_content<G, S>(green_color: G, spoiler_color: S) -> Vec<u8>
+pub fn coverpage_content<S>(href: S, (width, height): (usize, usize), language: &str) -> String
 where
-    G: AsRef<str>,
     S: AsRef<str>,
 {
-    let mut bytes: Vec<u8> = Vec::new();
-    bytes.extend(
-        include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/static/style.css")).as_slice(),
-    );
+    coverpage(
+        Tag::new("image")
+            .attribute("width", width)
+            .attribute("height", height)
+            .attribute("xlink:href", format!("../{}", href.as_ref())),
+        (width, height),
+        language,
+    )
+}
+
+/// Cover page with a generated cover drawn inline, so its text is kept as text.
+pub fn generated_coverpage_content(cover: &GeneratedCover, language: &str) -> String {
+    coverpage(cover.drawing(), (COVER_WIDTH, COVER_HEIGHT), language)
+}
+
+/// Look of a book, made of its default colors and rules added on top of the base stylesheet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Theme {
+    /// Green text on the background of the reader
+    #[default]
+    Default,
+    /// Colors of the imageboard pages with a cream background
+    Yotsuba,
+    /// Light text on a dark background
+    Dark,
+    /// Greentext marked with a border and spoilers with a dotted outline instead of colors,
+    /// for e-ink readers
+    Eink,
+}
+
+impl Theme {
+    const ALL: [Self; 4] = [Self::Default, Self::Yotsuba, Self::Dark, Self::Eink];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Default => "default",
+            Self::Yotsuba => "yotsuba",
+            Self::Dark => "dark",
+            Self::Eink => "eink",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|theme| theme.name() == name)
+    }
+
+    /// Values of the variables used by the rules of the theme, which can be overridden.
+    pub fn variables(self) -> Vec<(&'static str, &'static str)> {
+        let mut variables = vec![
+            ("green-color", self.green_color()),
+            ("spoiler-color", self.spoiler_color()),
+            ("text-color", "initial"),
+            ("post-spacing", "1em"),
+            ("post-header-size", "0.8em"),
+        ];
+        let mut set = |name, value| match variables.iter_mut().find(|(n, _)| *n == name) {
+            Some(variable) => variable.1 = value,
+            None => variables.push((name, value)),
+        };
+
+        match self {
+            Self::Default => {}
+            Self::Yotsuba => {
+                set("text-color", "#800000");
+                set("background-color", "#FFFFEE");
+                set("link-color", "#DD0000");
+                set("post-background-color", "#F0E0D6");
+                set("author-color", "#117743");
+            }
+            Self::Dark => {
+                set("text-color", "#C5C8C6");
+                set("background-color", "#1D1F21");
+                set("link-color", "#81A2BE");
+                set("post-header-color", "#969896");
+            }
+            Self::Eink => set("greentext-border", "0.2em solid"),
+        }
+        variables
+    }
+
+    /// Color of greentext unless one is given.
+    pub fn green_color(self) -> &'static str {
+        match self {
+            Self::Default => DEFAULT_GREEN_COLOR,
+            Self::Yotsuba => "#789922",
+            Self::Dark => "#B5BD68",
+            Self::Eink => "#000",
+        }
+    }
+
+    /// Color of spoilers unless one is given, which is the one of their outline in the e-ink
+    /// theme.
+    pub fn spoiler_color(self) -> &'static str {
+        match self {
+            Self::Default | Self::Yotsuba | Self::Eink => DEFAULT_SPOILER_COLOR,
+            Self::Dark => "#373B41",
+        }
+    }
+
+    /// Rules added after the ones for highlights and spoilers, which overrides them.
+    fn rules(self) -> &'static str {
+        match self {
+            Self::Default => "",
+            Self::Yotsuba => {
+                "body { background-color: var(--background-color); color: var(--text-color); }\n\
+                .quotelink { color: var(--link-color); }\n\
+                article.post { background-color: var(--post-background-color); padding: 0.25em 0.5em; }\n\
+                .post-author { color: var(--author-color); font-weight: bold; }"
+            }
+            Self::Dark => {
+                "body { background-color: var(--background-color); color: var(--text-color); }\n\
+                a, .quotelink { color: var(--link-color); }\n\
+                .post-header { color: var(--post-header-color); }"
+            }
+            Self::Eink => {
+                "p { border-left: var(--greentext-border); padding-left: 0.5em; }"
+            }
+        }
+    }
+}
+
+/// Replaces every `var(--name)` with the value of the variable, or with its fallback in
+/// `var(--name, fallback)` when there isn't one, leaving the rest as they are.
+fn resolve_variables<'a, F>(css: &str, value: F) -> String
+where
+    F: Fn(&str) -> Option<&'a str>,
+{
+    let regex = Regex::new(r"var\(\s*--([A-Za-z0-9_-]+)\s*(?:,\s*([^()]*?)\s*)?\)").unwrap();
+    regex
+        .replace_all(css, |captures: &regex::Captures| {
+            match (value(&captures[1]), captures.get(2)) {
+                (Some(value), _) => value.to_string(),
+                (None, Some(fallback)) => fallback.as_str().to_string(),
+                (None, None) => captures[0].to_string(),
+            }
+        })
+        .into_owned()
+}
+
+/// Stylesheet of a book, with the rules of its theme and then the extra stylesheets given.
+///
+/// Variables override the values of the ones of the theme, later ones taking precedence, and
+/// can also be used by the extra stylesheets. Fails if one of them isn't a variable of the
+/// theme and isn't used by any of the extra stylesheets either.
+pub fn stylesheet_content<S>(
+    spoiler_mode: SpoilerMode,
+    highlights: &[Highlight],
+    theme: Theme,
+    variables: &[(String, String)],
+    extra: &[S],
+) -> CliResult<Vec<u8>>
+where
+    S: AsRef<str>,
+{
+    let theme_variables = theme.variables();
+    for (name, _) in variables {
+        let reference = format!("var(--{}", name);
+        if theme_variables.iter().all(|(known, _)| known != name)
+            && !extra.iter().any(|css| css.as_ref().contains(&reference))
+        {
+            return Err(CliError::from(format!(
+                "unknown CSS variable {:?}, the {} theme has {}",
+                name,
+                theme.name(),
+                theme_variables
+                    .iter()
+                    .map(|(known, _)| format!("`{}`", known))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            )));
+        }
+    }
+
+    let mut css = String::from_utf8_lossy(include_bytes!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/static/style.css"
+    )))
+    .into_owned();
 
     // By default, highlight all paragraphs with green color and use a class to remove it.
     // This is because most of the lines are going to be highlighted in the majority of greens
     // anyways.
-    bytes.extend(
-        format!(
-            "p {{ color: {green_color}; }}\n\
-            .{reset_foreground_class} {{ color: initial; }}\n\
-            p > span {{ background-color: {spoiler_color}; color: transparent; }}",
-            green_color = green_color.as_ref(),
-            spoiler_color = spoiler_color.as_ref(),
-            reset_foreground_class = RESET_FOREGROUND_CLASS
-        )
-        .as_bytes(),
+    css.push_str(&format!(
+        "p {{ color: var(--green-color); }}\n\
+        .{} {{ color: var(--text-color); }}",
+        RESET_FOREGROUND_CLASS
+    ));
+
+    if spoiler_mode == SpoilerMode::Blackout {
+        // E-ink readers can't reveal hidden text, so spoilers are only outlined there.
+        css.push_str(match theme {
+            Theme::Eink => {
+                "\np span { outline-color: var(--spoiler-color); outline-style: dotted; \
+                outline-width: 1px; }"
+            }
+            _ => "\np span { background-color: var(--spoiler-color); color: transparent; }",
+        });
+    }
+
+    for highlight in highlights {
+        css.push_str(&format!(
+            "\n.{} {{ color: {}; }}",
+            highlight.class, highlight.color
+        ));
+    }
+
+    css.push_str(
+        "\narticle.post { margin-bottom: var(--post-spacing); }\n\
+        .post-header { font-size: var(--post-header-size); margin-bottom: 0.25em; }",
     );
 
-    bytes
+    if theme != Theme::Default {
+        css.push_str(&format!(
+            "\n{}{} */\n{}",
+            THEME_MARKER,
+            theme.name(),
+            theme.rules()
+        ));
+    }
+
+    // Only greentext gets the border, lines without highlight and other highlights don't.
+    if theme == Theme::Eink {
+        let classes: Vec<String> = std::iter::once(RESET_FOREGROUND_CLASS)
+            .chain(highlights.iter().map(|highlight| highlight.class.as_str()))
+            .map(|class| format!(".{}", class))
+            .collect();
+        css.push_str(&format!(
+            "\n{} {{ border-left: none; padding-left: 0; }}",
+            classes.join(", ")
+        ));
+    }
+
+    for stylesheet in extra {
+        css.push('\n');
+        css.push_str(stylesheet.as_ref());
+    }
+
+    let value = |name: &str| {
+        variables
+            .iter()
+            .rev()
+            .find(|(given, _)| given == name)
+            .map(|(_, value)| value.as_str())
+            .or_else(|| {
+                theme_variables
+                    .iter()
+                    .find(|(known, _)| *known == name)
+                    .map(|(_, value)| *value)
+            })
+    };
+    Ok(resolve_variables(&css, value).into_bytes())
 }
 
 pub struct PasteContent {
     title: String,
     body: Tag,
+    spoiler_mode: SpoilerMode,
+    post_headers: bool,
+    language: String,
+    notes: Vec<Tag>,
 }
 
 impl PasteContent {
@@ -108,9 +377,39 @@ impl PasteContent {
         Self {
             title: title.to_string(),
             body: Tag::new("body"),
+            spoiler_mode: SpoilerMode::default(),
+            post_headers: true,
+            language: DEFAULT_LANGUAGE.into(),
+            notes: Vec::new(),
         }
     }
 
+    pub fn spoiler_mode(&mut self, mode: SpoilerMode) -> &mut Self {
+        self.spoiler_mode = mode;
+        self
+    }
+
+    /// Whether posts start with a header showing who wrote them, when and their number.
+    pub fn post_headers(&mut self, post_headers: bool) -> &mut Self {
+        self.post_headers = post_headers;
+        self
+    }
+
+    /// Language of the text as a BCP 47 tag.
+    pub fn language<S>(&mut self, language: S) -> &mut Self
+    where
+        S: ToString,
+    {
+        self.language = language.to_string();
+        self
+    }
+
+    /// Adds a note with the text of a spoiler, as given by `LineParser::take_notes`.
+    pub fn add_note(&mut self, note: Tag) -> &mut Self {
+        self.notes.push(note);
+        self
+    }
+
     pub fn add_line<C>(&mut self, child: C) -> &mut Self
     where
         C: Into<Child>,
@@ -119,28 +418,124 @@ impl PasteContent {
         self
     }
 
-    pub fn build(self) -> String {
+    /// Adds a post as an `article` block with its lines, anchored so replies can link to it.
+    pub fn add_post<I>(&mut self, post: &Post, lines: I) -> &mut Self
+    where
+        I: IntoIterator<Item = Tag>,
+    {
+        let mut article = Tag::new("article");
+        article
+            .attribute("class", "post")
+            .attribute("id", post_anchor(post.number));
+
+        if self.post_headers {
+            let mut header = Tag::new("header");
+            header.attribute("class", "post-header").child(
+                Tag::new("span")
+                    .attribute("class", "post-author")
+                    .child(post.author.as_str()),
+            );
+            if let Some(tripcode) = &post.tripcode {
+                header.child(" ").child(
+                    Tag::new("span")
+                        .attribute("class", "post-tripcode")
+                        .child(tripcode.as_str()),
+                );
+            }
+            if let Some(time) = &post.time {
+                header.child(" ").child(
+                    Tag::new("time")
+                        .attribute("datetime", time)
+                        .child(time.as_str()),
+                );
+            }
+            header.child(" ").child(
+                Tag::new("span")
+                    .attribute("class", "post-number")
+                    .child(format!("{}{}", POST_NUMBER_PREFIX, post.number)),
+            );
+            article.child(header);
+        }
+
+        for line in lines {
+            article.child(line);
+        }
+
+        self.body.child(article);
+        self
+    }
+
+    pub fn build(mut self) -> String {
+        match self.spoiler_mode {
+            SpoilerMode::Footnote => {
+                for note in self.notes {
+                    self.body.child(note);
+                }
+            }
+            SpoilerMode::Endnote if !self.notes.is_empty() => {
+                let mut list = Tag::new("ol");
+                for note in self.notes {
+                    list.child(note);
+                }
+
+                self.body.child(
+                    Tag::new("section")
+                        .attribute("epub:type", "endnotes")
+                        .attribute("role", "doc-endnotes")
+                        .child(Tag::new("h2").child("Spoilers"))
+                        .child(list),
+                );
+            }
+            _ => {}
+        };
+
         let mut html = Tag::new("html");
         html.attribute("xmlns", NS_XHTML)
             .attribute("xmlns:epub", NS_OPS)
             .attribute(
                 "epub:prefix",
                 "z3998: http://www.daisy.org/z3998/2012/vocab/structure/#",
-            )
-            .attribute("lang", "en")
-            .attribute("xml:lang", "en")
-            .child(
-                Tag::new("head")
-                    .child(Tag::new("title").child(self.title))
-                    .child(
-                        Tag::new("link")
-                            .attribute("href", "../stylesheet.css")
-                            .attribute("rel", "stylesheet")
-                            .attribute("type", "text/css"),
-                    ),
-            )
-            .child(self.body);
+            );
+        language_attributes(&mut html, &self.language);
+        html.child(
+            Tag::new("head")
+                .child(Tag::new("title").child(self.title))
+                .child(
+                    Tag::new("link")
+                        .attribute("href", "../stylesheet.css")
+                        .attribute("rel", "stylesheet")
+                        .attribute("type", "text/css"),
+                ),
+        )
+        .child(self.body);
 
         xhtml_content_from_html_tag(html)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn eink_greentext_border() {
+        let css = stylesheet_content::<&str>(
+            SpoilerMode::Blackout,
+            &[
+                Highlight::orangetext("#E07000"),
+                Highlight::caret("#6C8EBF"),
+            ],
+            Theme::Eink,
+            &[],
+            &[],
+        )
+        .unwrap();
+        let css = String::from_utf8(css).unwrap();
+
+        assert!(css.contains("p { border-left: 0.2em solid; padding-left: 0.5em; }"));
+        assert!(
+            css.contains(".icolor, .orangetext, .caret { border-left: none; padding-left: 0; }")
+        );
+        assert!(!css.contains("var("));
+    }
+}
@@ -0,0 +1,562 @@
+//! Covers made up from the title, authors and series of books that don't have one, and
+//! conversion of the ones given as images.
+
+use std::io::Cursor;
+
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::png::{CompressionType, FilterType as PngFilter, PngEncoder};
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageDecoder, ImageReader};
+use resvg::{tiny_skia, usvg};
+
+use crate::book::Cover;
+use crate::errors::{CliError, CliResult, ResultExt};
+use crate::metadata::Metadata;
+use crate::tag::Tag;
+
+pub const COVER_WIDTH: usize = 1200;
+pub const COVER_HEIGHT: usize = 1800;
+/// Where the raster version of a generated cover is stored, which tells it apart from covers
+/// given as images.
+pub const GENERATED_COVER_FILE: &str = "img/generated-cover.png";
+
+/// Fonts of the text, the first one is embedded so the raster version looks the same
+/// everywhere and the rest are fallbacks for readers that don't have it.
+const FONT_FAMILY: &str = "'Noto Sans', 'DejaVu Sans', sans-serif";
+const MARGIN: f64 = 120.0;
+const MAX_TITLE_LINES: usize = 5;
+
+/// Where the text is placed on the cover.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CoverLayout {
+    /// Title in the middle with the authors under it, inside a frame
+    #[default]
+    Centered,
+    /// Title on a band across the cover
+    Band,
+    /// Title at the bottom left, next to a bar
+    Corner,
+}
+
+/// Colors of the cover.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CoverColors {
+    /// Green color of the book on a dark background
+    #[default]
+    Greentext,
+    /// Dark text on a white background
+    Light,
+    /// Light text on a dark background
+    Dark,
+    /// Colors of the imageboard pages with a cream background
+    Yotsuba,
+}
+
+struct Palette {
+    background: String,
+    title: String,
+    text: String,
+    accent: String,
+}
+
+impl Palette {
+    fn new(colors: CoverColors, green_color: &str) -> Self {
+        let palette = |background: &str, title: &str, text: &str, accent: &str| Self {
+            background: background.into(),
+            title: title.into(),
+            text: text.into(),
+            accent: accent.into(),
+        };
+
+        match colors {
+            CoverColors::Greentext => palette("#161616", green_color, "#E8E8E8", green_color),
+            CoverColors::Light => palette("#FAFAF7", "#1A1A1A", "#3A3A3A", "#4A4A4A"),
+            CoverColors::Dark => palette("#1D1F21", "#EAEAEA", "#C5C8C6", "#81A2BE"),
+            CoverColors::Yotsuba => palette("#FFFFEE", "#800000", "#117743", "#F0E0D6"),
+        }
+    }
+}
+
+/// Width of a text in the given font size, roughly, with wide characters like the ones of CJK
+/// scripts taking a whole em and the rest a bit more than half of it.
+fn text_width(text: &str, size: f64) -> f64 {
+    text.chars()
+        .map(|c| if c as u32 >= 0x2E80 { 1.0 } else { 0.56 })
+        .sum::<f64>()
+        * size
+}
+
+/// Breaks a text into lines that fit in the given width, between words when there are any.
+fn wrap(text: &str, size: f64, width: f64) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    let mut line = String::new();
+
+    for word in text.split_whitespace() {
+        let candidate = if line.is_empty() {
+            word.to_string()
+        } else {
+            format!("{} {}", line, word)
+        };
+        if text_width(&candidate, size) <= width {
+            line = candidate;
+            continue;
+        }
+
+        if !line.is_empty() {
+            lines.push(std::mem::take(&mut line));
+        }
+        // Words that don't fit in a line on their own are broken anywhere.
+        for c in word.chars() {
+            if !line.is_empty() && text_width(&format!("{}{}", line, c), size) > width {
+                lines.push(std::mem::take(&mut line));
+            }
+            line.push(c);
+        }
+    }
+
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+/// Lines of the title in the largest size, from `max_size` down to half of it, where they fit
+/// in the given width, which is how large the title is and how many lines it takes.
+fn fit_title(title: &str, max_size: f64, width: f64) -> (Vec<String>, f64) {
+    let mut size = max_size;
+    loop {
+        let mut lines = wrap(title, size, width);
+        if lines.len() <= MAX_TITLE_LINES - 2 || size <= max_size / 2.0 {
+            if lines.len() > MAX_TITLE_LINES {
+                lines.truncate(MAX_TITLE_LINES);
+                if let Some(last) = lines.last_mut() {
+                    last.push('…');
+                }
+            }
+            return (lines, size);
+        }
+        size -= max_size / 10.0;
+    }
+}
+
+/// A single line of text, cut short when it doesn't fit.
+fn fit_line(text: &str, size: f64, width: f64) -> String {
+    if text_width(text, size) <= width {
+        return text.to_string();
+    }
+
+    let mut line = String::new();
+    for c in text.chars() {
+        if text_width(&format!("{}{}…", line, c), size) > width {
+            break;
+        }
+        line.push(c);
+    }
+    format!("{}…", line.trim_end())
+}
+
+fn rect(x: f64, y: f64, width: f64, height: f64, fill: &str) -> Tag {
+    let mut rect = Tag::new("rect");
+    rect.attribute("x", x)
+        .attribute("y", y)
+        .attribute("width", width)
+        .attribute("height", height)
+        .attribute("fill", fill);
+    rect
+}
+
+/// Cover drawn as SVG from the metadata of a book.
+#[derive(Debug, Clone)]
+pub struct GeneratedCover {
+    drawing: Tag,
+}
+
+impl GeneratedCover {
+    pub fn new(
+        metadata: &Metadata,
+        layout: CoverLayout,
+        colors: CoverColors,
+        green_color: &str,
+    ) -> Self {
+        let palette = Palette::new(colors, green_color);
+        let (width, height) = (COVER_WIDTH as f64, COVER_HEIGHT as f64);
+
+        let authors = metadata
+            .authors()
+            .map(|author| author.name.as_str())
+            .collect::<Vec<&str>>()
+            .join(", ");
+        let series = metadata
+            .series
+            .as_ref()
+            .map(|series| match metadata.series_index {
+                Some(index) => format!("{} #{}", series, index),
+                None => series.clone(),
+            });
+
+        let mut drawing = Tag::new("g");
+        drawing.child(rect(0.0, 0.0, width, height, &palette.background));
+
+        let text =
+            |x: f64, y: f64, size: f64, anchor: &str, fill: &str, bold: bool, content: &str| {
+                let mut text = Tag::new("text");
+                text.attribute("x", x)
+                    .attribute("y", y)
+                    .attribute("font-family", FONT_FAMILY)
+                    .attribute("font-size", size)
+                    .attribute("font-weight", if bold { "bold" } else { "normal" })
+                    .attribute("text-anchor", anchor)
+                    .attribute("fill", fill)
+                    .child(content);
+                text
+            };
+
+        match layout {
+            CoverLayout::Centered => {
+                let inset = MARGIN / 2.0;
+                let mut frame = rect(
+                    inset,
+                    inset,
+                    width - inset * 2.0,
+                    height - inset * 2.0,
+                    "none",
+                );
+                frame
+                    .attribute("stroke", &palette.accent)
+                    .attribute("stroke-width", 8);
+                drawing.child(frame);
+
+                let (lines, size) = fit_title(&metadata.title, 130.0, width - MARGIN * 2.0);
+                let line_height = size * 1.2;
+                let mut y = height * 0.42 - line_height * (lines.len() as f64 - 1.0) / 2.0;
+                for line in &lines {
+                    drawing.child(text(
+                        width / 2.0,
+                        y,
+                        size,
+                        "middle",
+                        &palette.title,
+                        true,
+                        line,
+                    ));
+                    y += line_height;
+                }
+
+                y += size * 0.1;
+                drawing.child(rect(
+                    width / 2.0 - 100.0,
+                    y - 8.0,
+                    200.0,
+                    8.0,
+                    &palette.accent,
+                ));
+                y += 100.0;
+                let author_line = fit_line(&authors, 64.0, width - MARGIN * 2.0);
+                drawing.child(text(
+                    width / 2.0,
+                    y,
+                    64.0,
+                    "middle",
+                    &palette.text,
+                    false,
+                    &author_line,
+                ));
+
+                if let Some(series) = &series {
+                    let series_line = fit_line(series, 52.0, width - MARGIN * 2.0);
+                    drawing.child(text(
+                        width / 2.0,
+                        height - MARGIN - 20.0,
+                        52.0,
+                        "middle",
+                        &palette.text,
+                        false,
+                        &series_line,
+                    ));
+                }
+            }
+            CoverLayout::Band => {
+                let (lines, size) = fit_title(&metadata.title, 120.0, width - MARGIN * 2.0);
+                let line_height = size * 1.2;
+                let band_height = line_height * lines.len() as f64 + size;
+                let band_top = height * 0.45 - band_height / 2.0;
+                drawing.child(rect(0.0, band_top, width, band_height, &palette.accent));
+
+                let title_fill = match colors {
+                    CoverColors::Yotsuba => &palette.title,
+                    _ => &palette.background,
+                };
+                let mut y = band_top + size * 0.5 + size * 0.95;
+                for line in &lines {
+                    drawing.child(text(width / 2.0, y, size, "middle", title_fill, true, line));
+                    y += line_height;
+                }
+
+                let author_line = fit_line(&authors, 64.0, width - MARGIN * 2.0);
+                drawing.child(text(
+                    width / 2.0,
+                    band_top + band_height + 140.0,
+                    64.0,
+                    "middle",
+                    &palette.text,
+                    false,
+                    &author_line,
+                ));
+
+                if let Some(series) = &series {
+                    let series_line = fit_line(series, 52.0, width - MARGIN * 2.0);
+                    drawing.child(text(
+                        width / 2.0,
+                        band_top - 100.0,
+                        52.0,
+                        "middle",
+                        &palette.text,
+                        false,
+                        &series_line,
+                    ));
+                }
+            }
+            CoverLayout::Corner => {
+                drawing.child(rect(
+                    MARGIN / 2.0,
+                    MARGIN,
+                    24.0,
+                    height - MARGIN * 2.0,
+                    &palette.accent,
+                ));
+
+                let left = MARGIN + 30.0;
+                let text_width = width - left - MARGIN / 2.0;
+                if let Some(series) = &series {
+                    let series_line = fit_line(series, 52.0, text_width);
+                    drawing.child(text(
+                        left,
+                        MARGIN + 52.0,
+                        52.0,
+                        "start",
+                        &palette.text,
+                        false,
+                        &series_line,
+                    ));
+                }
+
+                let author_line = fit_line(&authors, 64.0, text_width);
+                let author_y = height - MARGIN;
+                drawing.child(text(
+                    left,
+                    author_y,
+                    64.0,
+                    "start",
+                    &palette.text,
+                    false,
+                    &author_line,
+                ));
+
+                let (lines, size) = fit_title(&metadata.title, 140.0, text_width);
+                let line_height = size * 1.15;
+                let mut y = author_y - 120.0 - line_height * (lines.len() as f64 - 1.0);
+                for line in &lines {
+                    drawing.child(text(left, y, size, "start", &palette.title, true, line));
+                    y += line_height;
+                }
+            }
+        }
+
+        Self { drawing }
+    }
+
+    /// Elements of the cover, to be placed in an SVG element with a view box of
+    /// [`COVER_WIDTH`] by [`COVER_HEIGHT`].
+    pub fn drawing(&self) -> &Tag {
+        &self.drawing
+    }
+
+    /// The cover as a standalone SVG document.
+    pub fn to_svg(&self) -> String {
+        let mut svg = Tag::new("svg");
+        svg.attribute("xmlns", "http://www.w3.org/2000/svg")
+            .attribute("version", "1.1")
+            .attribute("width", COVER_WIDTH)
+            .attribute("height", COVER_HEIGHT)
+            .attribute("viewBox", format!("0 0 {} {}", COVER_WIDTH, COVER_HEIGHT))
+            .child(&self.drawing);
+        svg.to_string()
+    }
+
+    /// The cover as a PNG image, for readers that don't show SVG.
+    pub fn to_png(&self) -> CliResult<Vec<u8>> {
+        let mut options = usvg::Options {
+            font_family: String::from("Noto Sans"),
+            ..usvg::Options::default()
+        };
+        let fonts = options.fontdb_mut();
+        fonts.load_font_data(notosans::REGULAR_TTF.to_vec());
+        fonts.load_font_data(notosans::BOLD_TTF.to_vec());
+        fonts.load_system_fonts();
+
+        let tree = usvg::Tree::from_str(&self.to_svg(), &options)
+            .map_err(|err| CliError::from(format!("failed to draw cover: {}", err)))?;
+        let mut pixmap = tiny_skia::Pixmap::new(COVER_WIDTH as u32, COVER_HEIGHT as u32)
+            .expect("cover size isn't zero");
+        resvg::render(&tree, tiny_skia::Transform::default(), &mut pixmap.as_mut());
+
+        pixmap
+            .encode_png()
+            .map_err(|err| CliError::from(format!("failed to encode cover: {}", err)))
+    }
+}
+
+/// Format cover images are converted to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CoverFormat {
+    #[default]
+    Jpeg,
+    Png,
+}
+
+/// How a cover image is converted before it is added to a book.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CoverNormalization {
+    pub format: CoverFormat,
+    /// Largest width and height, larger images are scaled down to fit keeping their aspect
+    /// ratio.
+    pub max_size: (u32, u32),
+    /// Quality of JPEG images, from 1 to 100.
+    pub quality: u8,
+}
+
+impl Default for CoverNormalization {
+    fn default() -> Self {
+        Self {
+            format: CoverFormat::default(),
+            max_size: (1600, 2400),
+            quality: 85,
+        }
+    }
+}
+
+impl Cover {
+    /// Converts the image as given, turning it first the way its EXIF data says, which is left
+    /// out along with any other metadata it has.
+    pub fn normalize(&self, normalization: &CoverNormalization) -> CliResult<Self> {
+        let image_error = |err: image::ImageError| CliError::from(err.to_string());
+
+        let decode = || -> image::ImageResult<DynamicImage> {
+            let mut decoder = ImageReader::new(Cursor::new(self.bytes()))
+                .with_guessed_format()?
+                .into_decoder()?;
+            let orientation = decoder.orientation()?;
+            let mut image = DynamicImage::from_decoder(decoder)?;
+            image.apply_orientation(orientation);
+            Ok(image)
+        };
+        let mut image = decode()
+            .map_err(image_error)
+            .context("failed to decode cover image")?;
+
+        let (max_width, max_height) = normalization.max_size;
+        if image.width() > max_width || image.height() > max_height {
+            image = image.resize(max_width, max_height, FilterType::Lanczos3);
+        }
+
+        let mut bytes = Vec::new();
+        let encoded = match normalization.format {
+            CoverFormat::Jpeg => {
+                // JPEG doesn't have transparency, so transparent areas are made white.
+                let mut background = DynamicImage::new_rgba8(image.width(), image.height());
+                background.as_mut_rgba8().unwrap().fill(255);
+                image::imageops::overlay(&mut background, &image, 0, 0);
+
+                background
+                    .into_rgb8()
+                    .write_with_encoder(JpegEncoder::new_with_quality(
+                        &mut bytes,
+                        normalization.quality,
+                    ))
+            }
+            CoverFormat::Png => image.write_with_encoder(PngEncoder::new_with_quality(
+                &mut bytes,
+                CompressionType::Best,
+                PngFilter::Adaptive,
+            )),
+        };
+        encoded
+            .map_err(image_error)
+            .context("failed to encode cover image")?;
+
+        debug!(
+            "Cover image normalized from {} to {} bytes",
+            self.bytes().len(),
+            bytes.len()
+        );
+        Cover::new(bytes)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::metadata::{Contributor, Role};
+
+    #[test]
+    fn wrap_title() {
+        assert_eq!(wrap("a b c", 10.0, 20.0), ["a b", "c"]);
+        assert_eq!(wrap("abcdefgh", 10.0, 20.0), ["abc", "def", "gh"]);
+        assert_eq!(wrap("日本語", 10.0, 25.0), ["日本", "語"]);
+        assert_eq!(fit_line("abcdefgh", 10.0, 30.0), "abcd…");
+    }
+
+    #[test]
+    fn draw_metadata() {
+        let metadata = Metadata {
+            title: String::from("Paste & more"),
+            contributors: vec![Contributor::new("Author", Role::Author)],
+            series: Some(String::from("Saga")),
+            series_index: Some(2.0),
+            ..Metadata::default()
+        };
+
+        for layout in [
+            CoverLayout::Centered,
+            CoverLayout::Band,
+            CoverLayout::Corner,
+        ] {
+            let svg =
+                GeneratedCover::new(&metadata, layout, CoverColors::Greentext, "#00FF00").to_svg();
+            assert!(svg.contains(">Paste &amp; more</text>"));
+            assert!(svg.contains(">Author</text>"));
+            assert!(svg.contains(">Saga #2</text>"));
+            assert!(svg.contains(r##"fill="#00FF00""##));
+        }
+    }
+
+    #[test]
+    fn normalize_cover() {
+        let mut image = image::RgbaImage::new(400, 200);
+        image.put_pixel(0, 0, image::Rgba([255, 0, 0, 255]));
+        let mut bytes = Vec::new();
+        image
+            .write_with_encoder(PngEncoder::new(&mut bytes))
+            .unwrap();
+        let cover = Cover::new(bytes).unwrap();
+
+        let jpeg = cover
+            .normalize(&CoverNormalization {
+                max_size: (100, 100),
+                ..CoverNormalization::default()
+            })
+            .unwrap();
+        assert_eq!(jpeg.extension(), "jpg");
+        assert_eq!(jpeg.dimensions(), (100, 50));
+
+        let png = cover
+            .normalize(&CoverNormalization {
+                format: CoverFormat::Png,
+                ..CoverNormalization::default()
+            })
+            .unwrap();
+        assert_eq!(png.extension(), "png");
+        // Smaller images aren't scaled up.
+        assert_eq!(png.dimensions(), (400, 200));
+    }
+}
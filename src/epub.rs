@@ -0,0 +1,846 @@
+//! EPUBs built by green2epub, read back to add chapters to them or to get their text back.
+
+use std::collections::{HashMap, VecDeque};
+use std::io::{Read, Seek};
+
+use html_escape::decode_html_entities;
+use regex::Regex;
+use scraper::{node::Node, ElementRef, Html, Selector};
+use zip::ZipArchive;
+
+use crate::book::{Chapter, Cover};
+use crate::content::{Theme, THEME_MARKER};
+use crate::cover::GENERATED_COVER_FILE;
+use crate::errors::{CliError, CliResult, ResultExt};
+use crate::metadata::{Contributor, Metadata, Role};
+use crate::parser::{Highlight, SpoilerMode, SpoilerSyntax, BBCODE_TAGS, POST_NUMBER_PREFIX};
+use crate::stylesheet::RESOURCES_DIR;
+
+const CONTAINER_FILE: &str = "META-INF/container.xml";
+const PASTE_FILE_PREFIX: &str = "paste-";
+const PASTE_FILE_SUFFIX: &str = ".xhtml";
+const REFINES_PATTERN: &str =
+    r##"<meta refines="#([^"]+)" property="([^"]+)"[^>]*>([^<]*)</meta>"##;
+const MODIFIED_PATTERN: &str = r#"<meta property="dcterms:modified">([^<]*)</meta>"#;
+
+fn selector(selector: &str) -> Selector {
+    Selector::parse(selector).expect("selectors are valid")
+}
+
+fn text_of(document: &Html, selector: &Selector) -> Vec<String> {
+    document
+        .select(selector)
+        .map(|element| element.text().collect::<String>().trim().to_string())
+        .filter(|text| !text.is_empty())
+        .collect()
+}
+
+/// Number of a chapter from the name of its `paste-NNN.xhtml` file.
+fn paste_number(href: &str) -> Option<usize> {
+    href.rsplit('/')
+        .next()?
+        .strip_prefix(PASTE_FILE_PREFIX)?
+        .strip_suffix(PASTE_FILE_SUFFIX)?
+        .parse()
+        .ok()
+}
+
+/// Writes chapters back in greentext format, with formatting and spoilers as BBCode tags.
+struct TextWriter {
+    spoiler_mode: SpoilerMode,
+    spoiler: SpoilerSyntax,
+    /// Lines of the spoiler notes by their `id`.
+    notes: HashMap<String, Vec<String>>,
+    /// Lines of a spoiler moved to a note that continue in the following paragraphs.
+    pending: VecDeque<String>,
+    lines: Vec<String>,
+}
+
+impl TextWriter {
+    fn new(spoiler_mode: SpoilerMode) -> Self {
+        Self {
+            spoiler_mode,
+            spoiler: SpoilerSyntax::bbcode(),
+            notes: HashMap::new(),
+            pending: VecDeque::new(),
+            lines: Vec::new(),
+        }
+    }
+
+    /// Keeps the lines of the footnotes and endnotes with the text of spoilers.
+    fn collect_notes(&mut self, document: &Html) {
+        let backlink = selector(r##"a[href^="#spoiler-ref-"]"##);
+
+        for note in document.select(&selector("aside[id], li[id]")) {
+            let id = note.value().id().unwrap_or_default();
+            let is_note = id
+                .strip_prefix("spoiler-")
+                .is_some_and(|number| number.parse::<usize>().is_ok());
+            if !is_note {
+                continue;
+            }
+
+            let lines = note
+                .select(&selector("p"))
+                .map(|paragraph| {
+                    let mut line = String::new();
+                    self.inline(paragraph, &mut line);
+                    // Endnotes end with a space before the link back to the spoiler.
+                    if paragraph.select(&backlink).next().is_some() && line.ends_with(' ') {
+                        line.pop();
+                    }
+                    line
+                })
+                .collect();
+            self.notes.insert(id.to_string(), lines);
+        }
+    }
+
+    fn inline(&mut self, element: ElementRef, out: &mut String) {
+        for child in element.children() {
+            let child = match child.value() {
+                Node::Text(text) => {
+                    out.push_str(text);
+                    continue;
+                }
+                Node::Element(_) => ElementRef::wrap(child).expect("node is an element"),
+                _ => continue,
+            };
+
+            let name = child.value().name();
+            if name == "span" {
+                let mut content = String::new();
+                self.inline(child, &mut content);
+
+                // Inline spoilers are enclosed in brackets, which tell if a spoiler that spans
+                // multiple lines opens or closes in this one. Otherwise each line gets its own.
+                let (opens, content) = match self.spoiler_mode {
+                    SpoilerMode::Inline => match content.strip_prefix('[') {
+                        Some(content) => (true, content),
+                        None => (false, content.as_str()),
+                    },
+                    _ => (true, content.as_str()),
+                };
+                let (closes, content) = match self.spoiler_mode {
+                    SpoilerMode::Inline => match content.strip_suffix(']') {
+                        Some(content) => (true, content),
+                        None => (false, content),
+                    },
+                    _ => (true, content),
+                };
+
+                if opens {
+                    out.push_str(&self.spoiler.open);
+                }
+                out.push_str(content);
+                if closes {
+                    out.push_str(&self.spoiler.close);
+                }
+            } else if let Some((_, open, close)) = BBCODE_TAGS
+                .iter()
+                .find(|(format, _, _)| format.tag_name() == name)
+            {
+                out.push_str(open);
+                self.inline(child, out);
+                out.push_str(close);
+            } else if name == "a" {
+                let note = child
+                    .value()
+                    .attr("href")
+                    .and_then(|href| href.strip_prefix('#'))
+                    .and_then(|id| self.notes.get(id));
+
+                match note {
+                    Some(lines) => {
+                        let mut lines: VecDeque<String> = lines.clone().into();
+                        out.push_str(&self.spoiler.open);
+                        out.push_str(&lines.pop_front().unwrap_or_default());
+                        if lines.is_empty() {
+                            out.push_str(&self.spoiler.close);
+                        }
+                        self.pending = lines;
+                    }
+                    None if child
+                        .value()
+                        .attr("href")
+                        .is_some_and(|href| href.starts_with("#spoiler-ref-")) => {}
+                    None => self.inline(child, out),
+                };
+            } else {
+                self.inline(child, out);
+            }
+        }
+    }
+
+    fn paragraph(&mut self, paragraph: ElementRef) {
+        let mut line = String::new();
+        if let Some(note_line) = self.pending.pop_front() {
+            line.push_str(&note_line);
+            if self.pending.is_empty() {
+                line.push_str(&self.spoiler.close);
+            }
+        }
+
+        self.inline(paragraph, &mut line);
+        self.lines.push(line);
+    }
+
+    fn block(&mut self, element: ElementRef) {
+        for child in element.children().filter_map(ElementRef::wrap) {
+            match child.value().name() {
+                "p" => self.paragraph(child),
+                "br" => self.lines.push(String::new()),
+                "article" => {
+                    if !self.lines.is_empty() {
+                        self.lines.push(String::new());
+                    }
+
+                    let header = child
+                        .children()
+                        .filter_map(ElementRef::wrap)
+                        .find(|element| element.value().name() == "header");
+                    let number = child
+                        .value()
+                        .id()
+                        .and_then(|id| id.strip_prefix('p'))
+                        .unwrap_or_default();
+                    self.lines.push(match header {
+                        Some(header) => header.text().collect::<String>().trim().to_string(),
+                        None => format!("{}{}", POST_NUMBER_PREFIX, number),
+                    });
+
+                    self.block(child);
+                }
+                // Already written as the first line of the post.
+                "header" => {}
+                // Spoiler notes, already written where they are referenced.
+                "aside" | "section" => {}
+                _ => self.block(child),
+            };
+        }
+    }
+}
+
+/// Files of a zip archive, read on demand.
+struct Archive<R> {
+    zip: ZipArchive<R>,
+}
+
+impl<R> Archive<R>
+where
+    R: Read + Seek,
+{
+    fn bytes(&mut self, name: &str) -> CliResult<Vec<u8>> {
+        let mut file = self
+            .zip
+            .by_name(name)
+            .context(format!("missing file in EPUB: {:?}", name))?;
+
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes)
+            .context(format!("failed to read file in EPUB: {:?}", name))?;
+        Ok(bytes)
+    }
+
+    fn text(&mut self, name: &str) -> CliResult<String> {
+        let bytes = self.bytes(name)?;
+        String::from_utf8(bytes)
+            .map_err(|err| CliError::from(err.to_string()))
+            .context(format!("failed to read file in EPUB: {:?}", name))
+    }
+}
+
+/// Chapter of an EPUB, with the content of its `paste-NNN.xhtml` file.
+#[derive(Debug, Clone)]
+pub struct ExistingChapter {
+    pub number: usize,
+    pub title: String,
+    pub xhtml: String,
+}
+
+impl ExistingChapter {
+    /// Chapter that is kept as is when the book is built again.
+    pub fn into_chapter(self) -> Chapter {
+        let document = Html::parse_document(&self.xhtml);
+        let anchors = document
+            .select(&selector("[id]"))
+            .filter_map(|element| element.value().id()?.strip_prefix('p')?.parse().ok())
+            .collect();
+
+        Chapter::rendered(self.title, self.number, self.xhtml, anchors)
+    }
+
+    /// Text of the chapter in greentext format, with formatting and spoilers written back as
+    /// BBCode tags. Posts are written after a line with their header.
+    pub fn text(&self, spoiler_mode: SpoilerMode) -> String {
+        let document = Html::parse_document(&self.xhtml);
+
+        let mut writer = TextWriter::new(spoiler_mode);
+        writer.collect_notes(&document);
+        if let Some(body) = document.select(&selector("body")).next() {
+            writer.block(body);
+        }
+
+        let mut text = writer.lines.join("\n");
+        text.push('\n');
+        text
+    }
+}
+
+/// Metadata of the book from its package document.
+///
+/// Dates that are the same as the modification date were made up when the book was built, so
+/// they are left out to be made up again.
+fn read_metadata(text: &str, package: &Html) -> CliResult<Metadata> {
+    let first = |name: &str| text_of(package, &selector(name)).into_iter().next();
+
+    let title = first(r"dc\:title")
+        .ok_or_else(|| CliError::from(String::from("EPUB doesn't have a title")))?;
+
+    // `meta` elements are empty in HTML, so their values are taken from the text.
+    let mut refines: HashMap<(String, String), String> = HashMap::new();
+    for captures in Regex::new(REFINES_PATTERN).unwrap().captures_iter(text) {
+        refines.insert(
+            (captures[1].to_string(), captures[2].to_string()),
+            decode_html_entities(&captures[3]).trim().to_string(),
+        );
+    }
+    let modified = Regex::new(MODIFIED_PATTERN)
+        .unwrap()
+        .captures(text)
+        .map(|captures| captures[1].trim().to_string());
+
+    let calibre_meta = |name: &str| {
+        package
+            .select(&selector(&format!(r#"meta[name="{}"]"#, name)))
+            .find_map(|meta| meta.value().attr("content"))
+            .map(str::trim)
+            .filter(|content| !content.is_empty())
+    };
+
+    let mut contributors = Vec::new();
+    for element in package.select(&selector(r"dc\:creator, dc\:contributor")) {
+        let name = element.text().collect::<String>().trim().to_string();
+        if name.is_empty() {
+            continue;
+        }
+
+        let id = element.value().id().unwrap_or_default().to_string();
+        let refined = |property: &str| refines.get(&(id.clone(), property.to_string()));
+        let role = match refined("role").and_then(|code| Role::from_marc_code(code)) {
+            Some(role) => role,
+            None if element.value().name() == "dc:creator" => Role::Author,
+            None => continue,
+        };
+
+        let mut contributor = Contributor::new(name, role);
+        contributor.file_as = refined("file-as").cloned();
+        contributors.push(contributor);
+    }
+
+    Ok(Metadata {
+        title,
+        contributors,
+        subjects: text_of(package, &selector(r"dc\:subject")),
+        description: first(r"dc\:description"),
+        publisher: first(r"dc\:publisher"),
+        language: first(r"dc\:language"),
+        date: first(r"dc\:date").filter(|date| Some(date) != modified.as_ref()),
+        rights: first(r"dc\:rights"),
+        source: first(r"dc\:source"),
+        identifier: first(r"dc\:identifier"),
+        series: calibre_meta("calibre:series").map(String::from),
+        series_index: calibre_meta("calibre:series_index").and_then(|index| index.parse().ok()),
+    })
+}
+
+/// Metadata, cover, stylesheet and chapters of an EPUB built by green2epub.
+#[derive(Debug, Clone)]
+pub struct ExistingBook {
+    pub metadata: Metadata,
+    pub cover: Option<Cover>,
+    pub stylesheet: Option<Vec<u8>>,
+    /// Files referred to by the stylesheet, with their path relative to it and media type.
+    pub resources: Vec<(String, Vec<u8>, String)>,
+    pub chapters: Vec<ExistingChapter>,
+}
+
+impl ExistingBook {
+    /// Reads the book, where chapters are the `paste-NNN.xhtml` files in the order of its spine.
+    pub fn from_reader<R>(reader: R) -> CliResult<Self>
+    where
+        R: Read + Seek,
+    {
+        let mut archive = Archive {
+            zip: ZipArchive::new(reader).context("failed to open EPUB")?,
+        };
+
+        let container = Html::parse_document(&archive.text(CONTAINER_FILE)?);
+        let package_path = container
+            .select(&selector("rootfile"))
+            .find_map(|rootfile| rootfile.value().attr("full-path"))
+            .ok_or_else(|| CliError::from(String::from("EPUB doesn't have a package document")))?
+            .to_string();
+        let base = match package_path.rsplit_once('/') {
+            Some((dir, _)) => format!("{}/", dir),
+            None => String::new(),
+        };
+
+        // Metadata was written without escaping by earlier versions, so the package document
+        // isn't always well formed XML and a forgiving parser is needed.
+        let package_text = archive.text(&package_path)?;
+        let package = Html::parse_document(&package_text);
+        let metadata = read_metadata(&package_text, &package)?;
+
+        let mut items: HashMap<String, String> = HashMap::new();
+        let mut cover_href = None;
+        let mut stylesheet_href = None;
+        let mut resource_hrefs = Vec::new();
+        for item in package.select(&selector("manifest item")) {
+            let item = item.value();
+            let (id, href) = match (item.attr("id"), item.attr("href")) {
+                (Some(id), Some(href)) => (id, href),
+                _ => continue,
+            };
+
+            if item
+                .attr("properties")
+                .is_some_and(|properties| properties.split_whitespace().any(|p| p == "cover-image"))
+            {
+                cover_href = Some(href.to_string());
+            }
+            if item.attr("media-type") == Some("text/css") && href == "stylesheet.css" {
+                stylesheet_href = Some(href.to_string());
+            }
+            if let (true, Some(mime_type)) = (
+                href.starts_with(&format!("{}/", RESOURCES_DIR)),
+                item.attr("media-type"),
+            ) {
+                resource_hrefs.push((href.to_string(), mime_type.to_string()));
+            }
+
+            items.insert(id.into(), href.into());
+        }
+
+        // Generated covers are left out so they are drawn again from the current metadata.
+        let cover = match cover_href {
+            Some(href) if href != GENERATED_COVER_FILE => {
+                Some(Cover::new(archive.bytes(&format!("{}{}", base, href))?)?)
+            }
+            _ => None,
+        };
+        let stylesheet = match stylesheet_href {
+            Some(href) => Some(archive.bytes(&format!("{}{}", base, href))?),
+            None => None,
+        };
+        let mut resources = Vec::new();
+        for (href, mime_type) in resource_hrefs {
+            let bytes = archive.bytes(&format!("{}{}", base, href))?;
+            resources.push((href, bytes, mime_type));
+        }
+
+        let mut chapters = Vec::new();
+        for itemref in package.select(&selector("spine itemref")) {
+            let href = match itemref
+                .value()
+                .attr("idref")
+                .and_then(|idref| items.get(idref))
+            {
+                Some(href) => href,
+                None => continue,
+            };
+            let number = match paste_number(href) {
+                Some(number) => number,
+                None => continue,
+            };
+
+            let xhtml = archive.text(&format!("{}{}", base, href))?;
+            let title = text_of(&Html::parse_document(&xhtml), &selector("head title"))
+                .into_iter()
+                .next()
+                .unwrap_or_else(|| format!("{:03}", number));
+
+            chapters.push(ExistingChapter {
+                number,
+                title,
+                xhtml,
+            });
+        }
+
+        if chapters.is_empty() {
+            return Err(CliError::from(String::from(
+                "no chapters found in EPUB, it wasn't built by green2epub",
+            )));
+        }
+        debug!("EPUB has {} chapters", chapters.len());
+
+        Ok(Self {
+            metadata,
+            cover,
+            stylesheet,
+            resources,
+            chapters,
+        })
+    }
+
+    /// How spoilers were rendered, as far as it can be told from the chapters and stylesheet.
+    pub fn spoiler_mode(&self) -> SpoilerMode {
+        let has = |text: &str| {
+            self.chapters
+                .iter()
+                .any(|chapter| chapter.xhtml.contains(text))
+        };
+
+        if has(r#"epub:type="noteref""#) {
+            SpoilerMode::Footnote
+        } else if has(r#"epub:type="endnotes""#) {
+            SpoilerMode::Endnote
+        } else if self.spoiler_color().is_some() {
+            SpoilerMode::Blackout
+        } else {
+            SpoilerMode::Inline
+        }
+    }
+
+    /// Theme the stylesheet was made with, books made before there were themes have the default
+    /// one.
+    pub fn theme(&self) -> Option<Theme> {
+        let stylesheet = String::from_utf8_lossy(self.stylesheet.as_ref()?);
+        match stylesheet.split_once(THEME_MARKER) {
+            Some((_, rest)) => rest
+                .split_once(" */")
+                .and_then(|(name, _)| Theme::from_name(name)),
+            None => Some(Theme::Default),
+        }
+    }
+
+    /// Color given to a property in a rule of the stylesheet, as written by green2epub.
+    fn stylesheet_color(&self, selector: &str, property: &str) -> Option<String> {
+        let stylesheet = String::from_utf8_lossy(self.stylesheet.as_ref()?);
+        let regex = Regex::new(&format!(
+            r"(?m)^{} \{{ {}: (#[0-9A-Fa-f]+);",
+            regex::escape(selector),
+            regex::escape(property)
+        ))
+        .expect("regex is valid");
+
+        regex
+            .captures(&stylesheet)
+            .map(|captures| captures[1].to_string())
+    }
+
+    pub fn green_color(&self) -> Option<String> {
+        self.stylesheet_color("p", "color")
+    }
+
+    /// Color of blacked out spoilers, or of their outline in the e-ink theme.
+    pub fn spoiler_color(&self) -> Option<String> {
+        self.stylesheet_color("p span", "background-color")
+            .or_else(|| self.stylesheet_color("p span", "outline-color"))
+    }
+
+    pub fn orange_color(&self) -> Option<String> {
+        self.stylesheet_color(&format!(".{}", Highlight::orangetext("").class), "color")
+    }
+
+    pub fn caret_color(&self) -> Option<String> {
+        self.stylesheet_color(&format!(".{}", Highlight::caret("").class), "color")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::book::{BookBuilder, Post};
+    use crate::cover::{CoverColors, CoverLayout};
+    use crate::parser::MarkedLine;
+    use crate::stylesheet::UserStylesheet;
+
+    fn built(builder: &mut BookBuilder) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        builder.build().unwrap().generate(&mut bytes).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn read_built_book() {
+        let bytes = built(
+            BookBuilder::new("Paste & more")
+                .author("Author")
+                .subject("SFW")
+                .chapter(Chapter::new("Part 1", "Anonymous No.100\n>be me"))
+                .chapter(Chapter::new("Part 2", ">>100\n>be spoiled")),
+        );
+
+        let book = ExistingBook::from_reader(Cursor::new(bytes)).unwrap();
+        assert_eq!(book.metadata.title, "Paste & more");
+        assert_eq!(
+            book.metadata.contributors,
+            [Contributor::new("Author", Role::Author)]
+        );
+        assert_eq!(book.metadata.subjects, ["SFW"]);
+        assert!(book.cover.is_none());
+        assert!(book.stylesheet.is_some());
+        assert_eq!(
+            book.chapters
+                .iter()
+                .map(|chapter| (chapter.number, chapter.title.as_str()))
+                .collect::<Vec<(usize, &str)>>(),
+            [(1, "Part 1"), (2, "Part 2")]
+        );
+    }
+
+    #[test]
+    fn read_metadata_back() {
+        let bytes = built(
+            BookBuilder::new("Paste")
+                .contributor(Contributor::new("John Doe", Role::Author).with_file_as("Doe, John"))
+                .contributor(Contributor::new("Translator", Role::Translator))
+                .description("A <story> & more")
+                .language("es")
+                .date("2021-03")
+                .source("https://example.com/thread/100")
+                .identifier("urn:isbn:9780000000000")
+                .series("Saga & more")
+                .series_index(2.0)
+                .chapter(Chapter::new("Part 1", ">be me")),
+        );
+
+        let metadata = ExistingBook::from_reader(Cursor::new(bytes))
+            .unwrap()
+            .metadata;
+        assert_eq!(
+            metadata.contributors,
+            [
+                Contributor::new("John Doe", Role::Author).with_file_as("Doe, John"),
+                Contributor::new("Translator", Role::Translator)
+            ]
+        );
+        assert_eq!(metadata.description.as_deref(), Some("A <story> & more"));
+        assert_eq!(metadata.language.as_deref(), Some("es"));
+        assert_eq!(metadata.date.as_deref(), Some("2021-03"));
+        assert_eq!(
+            metadata.source.as_deref(),
+            Some("https://example.com/thread/100")
+        );
+        assert_eq!(
+            metadata.identifier.as_deref(),
+            Some("urn:isbn:9780000000000")
+        );
+        assert_eq!(metadata.series.as_deref(), Some("Saga & more"));
+        assert_eq!(metadata.series_index, Some(2.0));
+
+        // Made up identifiers are kept so the book stays the same one, dates aren't.
+        let bytes = built(BookBuilder::new("Paste").chapter(Chapter::new("Part 1", ">be me")));
+        let metadata = ExistingBook::from_reader(Cursor::new(bytes))
+            .unwrap()
+            .metadata;
+        assert!(metadata
+            .identifier
+            .is_some_and(|identifier| identifier.starts_with("urn:uuid:")));
+        assert_eq!(metadata.date, None);
+    }
+
+    #[test]
+    fn append_to_built_book() {
+        let bytes = built(
+            BookBuilder::new("Paste")
+                .author("Author")
+                .chapter(Chapter::new("Part 1", "Anonymous No.100\n>>100")),
+        );
+        let book = ExistingBook::from_reader(Cursor::new(bytes)).unwrap();
+        let identifier = book.metadata.identifier.clone().unwrap();
+
+        let mut builder = BookBuilder::new(&book.metadata.title);
+        builder.identifier(&identifier);
+        for chapter in book.chapters {
+            builder.chapter(chapter.into_chapter());
+        }
+        builder.chapter(Chapter::new("Part 2", ">>100"));
+
+        let book = ExistingBook::from_reader(Cursor::new(built(&mut builder))).unwrap();
+        assert_eq!(book.metadata.identifier, Some(identifier));
+        assert_eq!(book.chapters.len(), 2);
+        assert_eq!(book.chapters[1].number, 2);
+        assert!(book.chapters[1]
+            .xhtml
+            .contains(r#"<a class="quotelink" href="paste-001.xhtml#p100">"#));
+    }
+
+    #[test]
+    fn generated_cover_isnt_kept() {
+        let bytes = built(
+            BookBuilder::new("Paste")
+                .author("Author")
+                .generate_cover(CoverLayout::Band, CoverColors::Light)
+                .chapter(Chapter::new("Part 1", ">be me")),
+        );
+
+        let mut archive = ZipArchive::new(Cursor::new(bytes.clone())).unwrap();
+        let mut cover = Vec::new();
+        archive
+            .by_name(&format!("OEBPS/{}", GENERATED_COVER_FILE))
+            .unwrap()
+            .read_to_end(&mut cover)
+            .unwrap();
+        assert!(cover.starts_with(b"\x89PNG"), "expected a PNG cover");
+
+        let mut coverpage = String::new();
+        archive
+            .by_name("OEBPS/content/cover.xhtml")
+            .unwrap()
+            .read_to_string(&mut coverpage)
+            .unwrap();
+        assert!(coverpage.contains(">Paste</text>"));
+        assert!(!coverpage.contains("<image"));
+
+        let book = ExistingBook::from_reader(Cursor::new(bytes)).unwrap();
+        assert!(book.cover.is_none());
+    }
+
+    #[test]
+    fn text_round_trip() {
+        let text = "Anonymous No.100\n>be [spoiler]a\nb\nc[/spoiler] d\n\n>[b]bold[/b] >>100\n";
+
+        for mode in [
+            SpoilerMode::Blackout,
+            SpoilerMode::Footnote,
+            SpoilerMode::Endnote,
+            SpoilerMode::Inline,
+        ] {
+            let build = |text: &str| {
+                let bytes = built(
+                    BookBuilder::new("Paste")
+                        .spoiler_mode(mode)
+                        .chapter(Chapter::new("001", text)),
+                );
+                ExistingBook::from_reader(Cursor::new(bytes)).unwrap()
+            };
+
+            let book = build(text);
+            assert_eq!(book.spoiler_mode(), mode);
+
+            let extracted = book.chapters[0].text(mode);
+            if mode != SpoilerMode::Blackout {
+                assert_eq!(extracted, text);
+            }
+            assert_eq!(build(&extracted).chapters[0].xhtml, book.chapters[0].xhtml);
+        }
+    }
+
+    #[test]
+    fn posts_text() {
+        let posts = vec![
+            Post {
+                number: 100,
+                author: "Anonymous".into(),
+                tripcode: Some("!Trip".into()),
+                lines: vec![MarkedLine::new().text(">be me").clone()],
+                ..Post::default()
+            },
+            Post {
+                number: 101,
+                author: "Anonymous".into(),
+                lines: vec![MarkedLine::new().post_ref(100).clone()],
+                ..Post::default()
+            },
+        ];
+        let bytes = built(BookBuilder::new("Paste").chapter(Chapter::from_posts("Thread", posts)));
+        let book = ExistingBook::from_reader(Cursor::new(bytes)).unwrap();
+
+        assert_eq!(
+            book.chapters[0].text(book.spoiler_mode()),
+            "Anonymous !Trip No.100\n>be me\n\nAnonymous No.101\n>>100\n"
+        );
+    }
+
+    #[test]
+    fn stylesheet_colors() {
+        let bytes = built(
+            BookBuilder::new("Paste")
+                .green_color("#0f0")
+                .highlight(Highlight::caret("#f0f"))
+                .chapter(Chapter::new("001", ">be me")),
+        );
+        let book = ExistingBook::from_reader(Cursor::new(bytes)).unwrap();
+
+        assert_eq!(book.green_color().as_deref(), Some("#0f0"));
+        assert_eq!(book.spoiler_color().as_deref(), Some("#000"));
+        assert_eq!(book.orange_color(), None);
+        assert_eq!(book.caret_color().as_deref(), Some("#f0f"));
+        assert_eq!(book.theme(), Some(Theme::Default));
+    }
+
+    #[test]
+    fn stylesheet_themes() {
+        let bytes = built(
+            BookBuilder::new("Paste")
+                .theme(Theme::Eink)
+                .chapter(Chapter::new("001", ">be me")),
+        );
+        let book = ExistingBook::from_reader(Cursor::new(bytes)).unwrap();
+
+        assert_eq!(book.theme(), Some(Theme::Eink));
+        assert_eq!(book.green_color().as_deref(), Some("#000"));
+        assert_eq!(book.spoiler_color().as_deref(), Some("#000"));
+        assert_eq!(book.spoiler_mode(), SpoilerMode::Blackout);
+        let stylesheet = String::from_utf8(book.stylesheet.unwrap()).unwrap();
+        assert!(stylesheet.contains("outline-style: dotted"));
+        assert!(!stylesheet.contains("color: transparent"));
+
+        // Colors given take precedence over the ones of the theme.
+        let bytes = built(
+            BookBuilder::new("Paste")
+                .theme(Theme::Dark)
+                .green_color("#0f0")
+                .chapter(Chapter::new("001", ">be me")),
+        );
+        let book = ExistingBook::from_reader(Cursor::new(bytes)).unwrap();
+
+        assert_eq!(book.theme(), Some(Theme::Dark));
+        assert_eq!(book.green_color().as_deref(), Some("#0f0"));
+        assert_eq!(
+            book.spoiler_color().as_deref(),
+            Some(Theme::Dark.spoiler_color())
+        );
+    }
+
+    #[test]
+    fn user_stylesheets() {
+        let mut stylesheet = UserStylesheet::new(String::from(
+            "body { background: url(img/bg.png); margin: var(--page-margin, 1em); }",
+        ))
+        .unwrap();
+        stylesheet
+            .resource("img/bg.png", b"image".to_vec())
+            .unwrap();
+        let mut builder = BookBuilder::new("Paste");
+        builder
+            .user_stylesheet(stylesheet)
+            .css_variable("post-spacing", "2em")
+            .css_variable("page-margin", "3em")
+            .chapter(Chapter::new("001", ">be me"));
+        let book = ExistingBook::from_reader(Cursor::new(built(&mut builder))).unwrap();
+
+        let stylesheet = String::from_utf8(book.stylesheet.unwrap()).unwrap();
+        assert!(stylesheet.contains("article.post { margin-bottom: 2em; }"));
+        assert!(stylesheet.ends_with("body { background: url(\"res/01-bg.png\"); margin: 3em; }"));
+        assert_eq!(
+            book.resources,
+            [(
+                String::from("res/01-bg.png"),
+                b"image".to_vec(),
+                String::from("image/png")
+            )]
+        );
+
+        // Variables have to be either of the theme or used by the stylesheets.
+        assert!(BookBuilder::new("Paste")
+            .css_variable("page-margin", "3em")
+            .chapter(Chapter::new("001", ">be me"))
+            .build()
+            .is_err());
+    }
+}
@@ -3,6 +3,8 @@ use std::{error::Error, fmt, io};
 use epub_builder::Error as EpubError;
 use imagesize::ImageError;
 use log::SetLoggerError;
+use serde_json::Error as JsonError;
+use zip::result::ZipError;
 
 pub type CliResult<T> = std::result::Result<T, CliError>;
 
@@ -12,6 +14,8 @@ pub enum ErrorKind {
     Log(SetLoggerError),
     Io(io::Error),
     Image(ImageError),
+    Json(JsonError),
+    Zip(ZipError),
     Msg(String),
 }
 
@@ -47,6 +51,8 @@ impl fmt::Display for CliError {
             Log(err) => writeln!(f, "Log error: {}", err)?,
             Io(err) => writeln!(f, "IO error: {}", err)?,
             Image(err) => writeln!(f, "Image error: {}", err)?,
+            Json(err) => writeln!(f, "JSON error: {}", err)?,
+            Zip(err) => writeln!(f, "Zip error: {}", err)?,
             Msg(msg) => writeln!(f, "{}", msg)?,
         };
 
@@ -67,6 +73,8 @@ impl Error for CliError {
             Log(err) => Some(err),
             Io(err) => Some(err),
             Image(err) => Some(err),
+            Json(err) => Some(err),
+            Zip(err) => Some(err),
             Msg(_) => None,
         }
     }
@@ -96,6 +104,18 @@ impl From<ImageError> for CliError {
     }
 }
 
+impl From<JsonError> for CliError {
+    fn from(error: JsonError) -> CliError {
+        CliError::with_kind(ErrorKind::Json(error))
+    }
+}
+
+impl From<ZipError> for CliError {
+    fn from(error: ZipError) -> CliError {
+        CliError::with_kind(ErrorKind::Zip(error))
+    }
+}
+
 impl From<String> for CliError {
     fn from(message: String) -> CliError {
         CliError::with_kind(ErrorKind::Msg(message))
@@ -0,0 +1,221 @@
+//! Threads saved in the JSON format of the 4chan read-only API.
+
+use std::time::{Duration, UNIX_EPOCH};
+
+use html_escape::decode_html_entities;
+use scraper::Html;
+use serde::Deserialize;
+
+use crate::archive::body_lines;
+use crate::book;
+use crate::errors::{CliResult, ResultExt};
+
+const DEFAULT_NAME: &str = "Anonymous";
+
+/// Post of a thread, only with the fields that are needed.
+#[derive(Deserialize, Debug, Clone)]
+pub struct Post {
+    pub no: u64,
+    pub name: Option<String>,
+    pub trip: Option<String>,
+    pub id: Option<String>,
+    pub sub: Option<String>,
+    pub com: Option<String>,
+    /// UNIX timestamp of the post.
+    pub time: Option<u64>,
+}
+
+impl Post {
+    pub fn name(&self) -> &str {
+        self.name.as_deref().unwrap_or(DEFAULT_NAME)
+    }
+
+    pub fn poster(&self) -> Poster<'_> {
+        Poster {
+            name: self.name(),
+            trip: self.trip.as_deref(),
+            id: self.id.as_deref(),
+        }
+    }
+
+    /// The post with its comment decoded back into lines of markup.
+    pub fn to_post(&self) -> book::Post {
+        book::Post {
+            number: self.no,
+            author: decode_html_entities(self.name()).into_owned(),
+            tripcode: self.trip.clone(),
+            time: self.time.map(|time| {
+                humantime::format_rfc3339_seconds(UNIX_EPOCH + Duration::from_secs(time))
+                    .to_string()
+            }),
+            lines: self
+                .com
+                .as_deref()
+                .map(|com| body_lines(Html::parse_fragment(com).root_element()))
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// Which posts of a thread make up the story.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum PostFilter {
+    /// Posts with the same tripcode as the OP if it has one, otherwise with the same ID if the
+    /// board shows them and with the same name as a last resort.
+    #[default]
+    Op,
+    OpName,
+    OpTrip,
+    OpId,
+    /// Posts with the given numbers.
+    Numbers(Vec<u64>),
+}
+
+impl PostFilter {
+    /// Tells if the post with the given number is kept, knowing who wrote it and who started
+    /// the thread.
+    pub fn keeps(&self, number: u64, poster: Poster, op: Poster) -> bool {
+        let filter = match self {
+            PostFilter::Op if op.trip.is_some() => &PostFilter::OpTrip,
+            PostFilter::Op if op.id.is_some() => &PostFilter::OpId,
+            PostFilter::Op => &PostFilter::OpName,
+            filter => filter,
+        };
+
+        match filter {
+            PostFilter::OpName => poster.name == op.name,
+            PostFilter::OpTrip => poster.trip.is_some() && poster.trip == op.trip,
+            PostFilter::OpId => poster.id.is_some() && poster.id == op.id,
+            PostFilter::Numbers(numbers) => numbers.contains(&number),
+            PostFilter::Op => unreachable!(),
+        }
+    }
+}
+
+/// Who wrote a post, as far as telling posters apart goes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Poster<'a> {
+    pub name: &'a str,
+    pub trip: Option<&'a str>,
+    pub id: Option<&'a str>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Thread {
+    pub posts: Vec<Post>,
+}
+
+impl Thread {
+    pub fn from_json(json: &str) -> CliResult<Self> {
+        let thread: Thread =
+            serde_json::from_str(json).context("failed to parse thread in 4chan API format")?;
+        debug!("Thread has {} posts", thread.posts.len());
+
+        Ok(thread)
+    }
+
+    pub fn op(&self) -> Option<&Post> {
+        self.posts.first()
+    }
+
+    /// Subject of the thread, if the OP has one.
+    pub fn subject(&self) -> Option<String> {
+        self.op()
+            .and_then(|op| op.sub.as_deref())
+            .map(|sub| decode_html_entities(sub).into_owned())
+    }
+
+    /// Posts that pass the filter, with their comments decoded.
+    pub fn posts(&self, filter: &PostFilter) -> Vec<book::Post> {
+        let op = match self.op() {
+            Some(op) => op.poster(),
+            None => return Vec::new(),
+        };
+
+        let posts: Vec<book::Post> = self
+            .posts
+            .iter()
+            .filter(|post| filter.keeps(post.no, post.poster(), op))
+            .map(Post::to_post)
+            .collect();
+        debug!("Kept {} posts of the thread", posts.len());
+
+        posts
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::MarkedLine;
+
+    const THREAD: &str = r##"{"posts": [
+        {"no": 100, "name": "Anonymous", "trip": "!Trip", "time": 1433971303, "sub": "Green &amp; story", "com": "<span class=\"quote\">&gt;be me</span><br><span class=\"quote\">&gt;be <s>spoiled</s></span>"},
+        {"no": 101, "name": "Anonymous", "com": "<a href=\"#p100\" class=\"quotelink\">&gt;&gt;100</a><br>cont<wbr>inue"},
+        {"no": 102, "name": "Anonymous", "trip": "!Trip", "com": "<span class=\"quote\">&gt;end</span>"}
+    ]}"##;
+
+    #[test]
+    fn comment_decoding() {
+        let lines = |com: &str| {
+            Post {
+                no: 1,
+                name: None,
+                trip: None,
+                id: None,
+                sub: None,
+                com: Some(com.into()),
+                time: None,
+            }
+            .to_post()
+            .lines
+        };
+
+        assert_eq!(
+            lines("<span class=\"quote\">&gt;be me</span><br><br>a <s>[spoiler]</s>"),
+            [
+                MarkedLine::new().text(">be me").clone(),
+                MarkedLine::new(),
+                MarkedLine::new()
+                    .text("a ")
+                    .open_spoiler()
+                    .text("[spoiler]")
+                    .close_spoiler()
+                    .clone()
+            ]
+        );
+        assert_eq!(
+            lines("<a href=\"#p1\" class=\"quotelink\">&gt;&gt;1</a> 1 &lt; 2<wbr>0"),
+            [MarkedLine::new()
+                .post_ref(1)
+                .text(" 1 < 2")
+                .text("0")
+                .clone()]
+        );
+        assert_eq!(
+            lines("<pre class=\"prettyprint\">x</pre>"),
+            [MarkedLine::new().open_code().text("x").close_code().clone()]
+        );
+    }
+
+    #[test]
+    fn op_posts() {
+        let thread = Thread::from_json(THREAD).unwrap();
+        let numbers = |filter: PostFilter| -> Vec<u64> {
+            thread
+                .posts(&filter)
+                .iter()
+                .map(|post| post.number)
+                .collect()
+        };
+
+        assert_eq!(thread.subject().as_deref(), Some("Green & story"));
+        assert_eq!(numbers(PostFilter::Op), [100, 102]);
+        assert_eq!(numbers(PostFilter::OpName), [100, 101, 102]);
+        assert_eq!(numbers(PostFilter::Numbers(vec![101])), [101]);
+
+        let op = &thread.posts(&PostFilter::Op)[0];
+        assert_eq!(op.tripcode.as_deref(), Some("!Trip"));
+        assert_eq!(op.time.as_deref(), Some("2015-06-10T21:21:43Z"));
+    }
+}
@@ -0,0 +1,270 @@
+//! Expansion of directories and glob patterns given as input files.
+
+use std::{
+    cmp::Ordering,
+    fs::{metadata, read_dir},
+    path::{Path, PathBuf},
+};
+
+use glob::{glob_with, MatchOptions};
+use green2epub::{CliError, CliResult, ResultExt};
+use regex::Regex;
+
+use crate::args::SortOrder;
+use crate::manifest::is_stdin;
+
+const GLOB_CHARS: [char; 3] = ['*', '?', '['];
+const DATE_PATTERN: &str = r"(\d{4})[-_.]?(\d{2})[-_.]?(\d{2})";
+
+/// Year, month and day.
+type Date = (u32, u32, u32);
+
+/// Compares strings so the numbers in them are compared by their value instead of character
+/// by character.
+fn natural_cmp(a: &str, b: &str) -> Ordering {
+    fn chunks(text: &str) -> Vec<&str> {
+        let mut chunks = Vec::new();
+        let mut start = 0;
+        for (idx, c) in text.char_indices().skip(1) {
+            let previous = text[..idx].chars().next_back().unwrap();
+            if previous.is_ascii_digit() != c.is_ascii_digit() {
+                chunks.push(&text[start..idx]);
+                start = idx;
+            }
+        }
+        if start < text.len() {
+            chunks.push(&text[start..]);
+        }
+        chunks
+    }
+
+    let (a_chunks, b_chunks) = (chunks(a), chunks(b));
+    for (a_chunk, b_chunk) in a_chunks.iter().zip(b_chunks.iter()) {
+        let is_number = |chunk: &str| chunk.bytes().all(|byte| byte.is_ascii_digit());
+
+        let ordering = if is_number(a_chunk) && is_number(b_chunk) {
+            let a_number = a_chunk.trim_start_matches('0');
+            let b_number = b_chunk.trim_start_matches('0');
+            a_number
+                .len()
+                .cmp(&b_number.len())
+                .then_with(|| a_number.cmp(b_number))
+        } else {
+            a_chunk.cmp(b_chunk)
+        };
+
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+
+    a_chunks.len().cmp(&b_chunks.len()).then_with(|| a.cmp(b))
+}
+
+/// Date written in a file name as `YYYY-MM-DD` or `YYYYMMDD`, where the separators can also be
+/// underscores or dots.
+fn date_in_name(regex: &Regex, name: &str) -> Option<Date> {
+    regex.captures_iter(name).find_map(|captures| {
+        let part = |idx: usize| captures[idx].parse::<u32>().ok();
+        let (year, month, day) = (part(1)?, part(2)?, part(3)?);
+
+        ((1..=12).contains(&month) && (1..=31).contains(&day)).then_some((year, month, day))
+    })
+}
+
+fn name_of(path: &Path) -> String {
+    path.to_string_lossy().into_owned()
+}
+
+/// Sorts the files in the given order, with ties broken by their natural order.
+fn sort(files: &mut Vec<PathBuf>, order: SortOrder) -> CliResult<()> {
+    match order {
+        SortOrder::Natural => files.sort_by(|a, b| natural_cmp(&name_of(a), &name_of(b))),
+        SortOrder::Lexical => files.sort(),
+        SortOrder::Mtime => {
+            let mut timed = Vec::with_capacity(files.len());
+            for file in files.drain(..) {
+                let modified = metadata(&file)
+                    .and_then(|metadata| metadata.modified())
+                    .context(format!(
+                        "failed to get modification time of input file: {:?}",
+                        file.display()
+                    ))?;
+                timed.push((modified, file));
+            }
+
+            timed.sort_by(|(a_time, a), (b_time, b)| {
+                a_time
+                    .cmp(b_time)
+                    .then_with(|| natural_cmp(&name_of(a), &name_of(b)))
+            });
+            files.extend(timed.into_iter().map(|(_, file)| file));
+        }
+        SortOrder::Date => {
+            let regex = Regex::new(DATE_PATTERN).unwrap();
+            let mut dated: Vec<(Option<Date>, PathBuf)> = files
+                .drain(..)
+                .map(|file| {
+                    let name = file.file_name().unwrap_or_default().to_string_lossy();
+                    let date = date_in_name(&regex, &name);
+                    if date.is_none() {
+                        warn!(
+                            "No date found in file name, placing it last: {:?}",
+                            file.display()
+                        );
+                    }
+                    (date, file)
+                })
+                .collect();
+
+            // Files without a date go last.
+            dated.sort_by(|(a_date, a), (b_date, b)| {
+                a_date
+                    .is_none()
+                    .cmp(&b_date.is_none())
+                    .then_with(|| a_date.cmp(b_date))
+                    .then_with(|| natural_cmp(&name_of(a), &name_of(b)))
+            });
+            files.extend(dated.into_iter().map(|(_, file)| file));
+        }
+    };
+
+    Ok(())
+}
+
+/// Files of a directory, leaving out hidden ones and subdirectories.
+fn files_in(dir: &Path) -> CliResult<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for entry in read_dir(dir).context(format!("failed to read directory: {:?}", dir.display()))? {
+        let entry = entry.context(format!("failed to read directory: {:?}", dir.display()))?;
+        let path = entry.path();
+
+        let is_hidden = entry.file_name().to_string_lossy().starts_with('.');
+        if !is_hidden && path.is_file() {
+            files.push(path);
+        }
+    }
+
+    Ok(files)
+}
+
+/// Files matched by a glob pattern, leaving out directories. As in shells, hidden files are
+/// only matched by patterns that start them with a dot.
+fn files_matching(pattern: &str) -> CliResult<Vec<PathBuf>> {
+    let options = MatchOptions {
+        require_literal_leading_dot: true,
+        ..MatchOptions::new()
+    };
+    let paths = glob_with(pattern, options)
+        .map_err(|err| CliError::from(err.to_string()))
+        .context(format!("invalid glob pattern: {:?}", pattern))?;
+
+    let mut files = Vec::new();
+    for path in paths {
+        let path = path
+            .map_err(|err| CliError::from(err.to_string()))
+            .context(format!("failed to expand glob pattern: {:?}", pattern))?;
+        if path.is_file() {
+            files.push(path);
+        }
+    }
+
+    Ok(files)
+}
+
+/// Expands a directory to the files in it and a glob pattern to the files it matches, both
+/// sorted in the given order. Anything else is left as is.
+pub fn expand(path: &Path, order: SortOrder) -> CliResult<Vec<PathBuf>> {
+    if is_stdin(path) {
+        return Ok(vec![path.into()]);
+    }
+
+    let pattern = path.to_string_lossy();
+    let mut files = if path.is_dir() {
+        debug!("Expanding directory {:?}", path.display());
+        let files = files_in(path)?;
+        if files.is_empty() {
+            return Err(CliError::from(format!(
+                "no files found in directory: {:?}",
+                path.display()
+            )));
+        }
+        files
+    } else if !path.exists() && pattern.contains(GLOB_CHARS) {
+        debug!("Expanding glob pattern {:?}", pattern);
+        let files = files_matching(&pattern)?;
+        if files.is_empty() {
+            return Err(CliError::from(format!(
+                "no files match glob pattern: {:?}",
+                pattern
+            )));
+        }
+        files
+    } else {
+        return Ok(vec![path.into()]);
+    };
+
+    sort(&mut files, order)?;
+    Ok(files)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::fs::{create_dir_all, remove_dir_all, write};
+
+    #[test]
+    fn natural_order() {
+        let mut names = ["10.txt", "2.txt", "1.txt", "part-10", "part-9", "part-09b"];
+        names.sort_by(|a, b| natural_cmp(a, b));
+        assert_eq!(
+            names,
+            ["1.txt", "2.txt", "10.txt", "part-9", "part-09b", "part-10"]
+        );
+    }
+
+    #[test]
+    fn dates_in_names() {
+        let regex = Regex::new(DATE_PATTERN).unwrap();
+        assert_eq!(
+            date_in_name(&regex, "paste-2021-03-04.txt"),
+            Some((2021, 3, 4))
+        );
+        assert_eq!(
+            date_in_name(&regex, "20210304_paste.txt"),
+            Some((2021, 3, 4))
+        );
+        assert_eq!(date_in_name(&regex, "paste-2021-13-04.txt"), None);
+        assert_eq!(date_in_name(&regex, "paste-001.txt"), None);
+    }
+
+    #[test]
+    fn directories_and_globs() {
+        let dir = std::env::temp_dir().join(format!("green2epub-inputs-{}", std::process::id()));
+        create_dir_all(dir.join("extra")).unwrap();
+        for name in ["10.txt", "2.txt", "1.md", ".hidden.txt"] {
+            write(dir.join(name), "").unwrap();
+        }
+
+        let names = |files: Vec<PathBuf>| -> Vec<String> {
+            files
+                .iter()
+                .map(|file| file.file_name().unwrap().to_string_lossy().into_owned())
+                .collect()
+        };
+
+        assert_eq!(
+            names(expand(&dir, SortOrder::Natural).unwrap()),
+            ["1.md", "2.txt", "10.txt"]
+        );
+        assert_eq!(
+            names(expand(&dir.join("*.txt"), SortOrder::Lexical).unwrap()),
+            ["10.txt", "2.txt"]
+        );
+        assert!(expand(&dir.join("*.epub"), SortOrder::Natural).is_err());
+        assert!(expand(&dir.join("extra"), SortOrder::Natural).is_err());
+
+        remove_dir_all(&dir).unwrap();
+    }
+}
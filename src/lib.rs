@@ -0,0 +1,41 @@
+//! Create EPUBs from text in greentext format.
+//!
+//! The entry point is [`BookBuilder`], which takes the metadata of the book and its chapters
+//! as in-memory text and builds a [`Book`] that can be written to anything implementing
+//! [`Write`](std::io::Write).
+//!
+//! ```no_run
+//! use green2epub::{BookBuilder, Chapter};
+//!
+//! let mut book = BookBuilder::new("Paste")
+//!     .author("Author")
+//!     .chapter(Chapter::new("001", ">be me\n>write a library"))
+//!     .build()
+//!     .unwrap();
+//!
+//! let mut bytes: Vec<u8> = Vec::new();
+//! book.generate(&mut bytes).unwrap();
+//! ```
+
+#[macro_use]
+extern crate log;
+
+pub mod archive;
+pub mod book;
+pub mod check;
+pub mod content;
+pub mod cover;
+pub mod epub;
+pub mod errors;
+pub mod fourchan;
+pub mod metadata;
+mod package;
+pub mod parser;
+pub mod stylesheet;
+pub mod tag;
+
+pub use book::{Book, BookBuilder, Chapter, Cover, Post, Progress};
+pub use content::Theme;
+pub use errors::{CliError, CliResult, ErrorKind, ResultExt};
+pub use metadata::{Contributor, Metadata, Role};
+pub use parser::{Highlight, MarkedLine, SpoilerMode, SpoilerSyntax};
@@ -2,128 +2,596 @@
 extern crate log;
 
 use std::{
-    fs::{read_to_string, File, OpenOptions},
-    io::Read,
-    path::PathBuf,
+    borrow::Cow,
+    env,
+    fs::{create_dir_all, read, read_to_string, write, File, OpenOptions},
+    io::{self, BufReader, Read, Write},
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
 use clap::Parser;
 use console::style;
-use epub_builder::{EpubBuilder, EpubContent, EpubVersion, ReferenceType, ZipLibrary};
-use indicatif::{ProgressBar, ProgressFinish, ProgressIterator, ProgressStyle};
+use green2epub::{
+    archive,
+    check::{check, Diagnostic},
+    content, cover,
+    epub::ExistingBook,
+    fourchan::{self, PostFilter},
+    stylesheet::UserStylesheet,
+    BookBuilder, Chapter, CliError, CliResult, Contributor, Cover, Highlight, Progress, ResultExt,
+    Role, SpoilerMode, SpoilerSyntax,
+};
+use indicatif::{ProgressBar, ProgressFinish, ProgressStyle};
 use log::Level;
+use regex::Regex;
+use serde_json::json;
 
 mod args;
-mod content;
-mod errors;
+mod inputs;
 mod logger;
-mod parser;
-mod tag;
+mod manifest;
 
-use args::Args;
-use content::{coverpage_content, stylesheet_content, PasteContent, COVER_STYLESHEET};
-use errors::{CliError, CliResult, ResultExt};
-use parser::LineParser;
-use tag::Tag;
+use args::{
+    Args, BookArgs, CheckArgs, Command, CoverColors, CoverFormat, CoverLayout, ExtractArgs,
+    InputFormat, OpMatch, ReportFormat, SortOrder, Spoiler, SpoilerRendering, Theme,
+};
+use manifest::{is_stdin, Manifest, ManifestChapter};
+
+/// Name of the manifest written along with the chapters of an extracted book.
+const MANIFEST_FILE: &str = "manifest.toml";
+/// Title of the chapter read from standard input, unless one is given.
+const STDIN_TITLE: &str = "stdin";
+/// Where the chapter read from standard input comes from, as shown in warnings.
+const STDIN_SOURCE: &str = "<stdin>";
+/// Environment variable with the modification date of reproducible books, as a Unix timestamp.
+const SOURCE_DATE_EPOCH: &str = "SOURCE_DATE_EPOCH";
+/// Modification date of reproducible books when `SOURCE_DATE_EPOCH` isn't set, 1980-01-01 is the
+/// earliest time zip files can store.
+const REPRODUCIBLE_DATE: u64 = 315_532_800;
+
+/// Date given in `SOURCE_DATE_EPOCH`, if it is set.
+fn source_date_epoch() -> CliResult<Option<SystemTime>> {
+    let value = match env::var(SOURCE_DATE_EPOCH) {
+        Ok(value) if !value.trim().is_empty() => value,
+        _ => return Ok(None),
+    };
+
+    let seconds = value.trim().parse::<u64>().map_err(|_| {
+        CliError::from(format!(
+            "{} must be a number of seconds since the Unix epoch: {:?}",
+            SOURCE_DATE_EPOCH, value
+        ))
+    })?;
 
-fn run(args: Args) -> CliResult<()> {
-    logger::init(args.verbose, args.quiet, args.color)?;
+    Ok(Some(UNIX_EPOCH + Duration::from_secs(seconds)))
+}
 
-    debug!("Parsed arguments: {:?}", args);
+/// Theme of a book as a flag value, which is left out for the default one.
+fn theme_arg(theme: content::Theme) -> Option<Theme> {
+    match theme {
+        content::Theme::Default => None,
+        content::Theme::Yotsuba => Some(Theme::Yotsuba),
+        content::Theme::Dark => Some(Theme::Dark),
+        content::Theme::Eink => Some(Theme::Eink),
+    }
+}
+
+fn spoiler_rendering(mode: SpoilerMode) -> SpoilerRendering {
+    match mode {
+        SpoilerMode::Blackout => SpoilerRendering::Blackout,
+        SpoilerMode::Footnote => SpoilerRendering::Footnote,
+        SpoilerMode::Endnote => SpoilerRendering::Endnote,
+        SpoilerMode::Inline => SpoilerRendering::Inline,
+    }
+}
+
+fn spoiler_syntax(syntax: Spoiler) -> SpoilerSyntax {
+    match syntax {
+        Spoiler::Bbcode => SpoilerSyntax::bbcode(),
+        Spoiler::BbcodeShort => SpoilerSyntax::bbcode_short(),
+        Spoiler::Discord => SpoilerSyntax::discord(),
+        Spoiler::Reddit => SpoilerSyntax::reddit(),
+    }
+}
+
+/// Format of an input file, recognized from its extension unless it is given.
+fn input_format(format: Option<InputFormat>, path: &Path) -> InputFormat {
+    match format.unwrap_or(InputFormat::Auto) {
+        InputFormat::Auto => match path.extension().and_then(|extension| extension.to_str()) {
+            Some(extension) if extension.eq_ignore_ascii_case("json") => InputFormat::FourchanJson,
+            Some(extension)
+                if extension.eq_ignore_ascii_case("html")
+                    || extension.eq_ignore_ascii_case("htm") =>
+            {
+                InputFormat::ArchiveHtml
+            }
+            _ => InputFormat::Text,
+        },
+        format => format,
+    }
+}
+
+/// Title made from the capture groups that matched, or the whole match if there aren't any.
+fn title_from_file_name(regex: &Regex, name: &str) -> Option<String> {
+    let captures = regex.captures(name)?;
+
+    let title = match captures.len() {
+        1 => captures[0].to_string(),
+        _ => captures
+            .iter()
+            .skip(1)
+            .flatten()
+            .map(|group| group.as_str())
+            .collect::<Vec<&str>>()
+            .join(" "),
+    };
+
+    Some(title)
+}
+
+fn read_epub(path: &str) -> CliResult<ExistingBook> {
+    debug!("Opening EPUB {:?}", path);
+    let file = File::open(path).context(format!("failed to open EPUB: {:?}", path))?;
+
+    ExistingBook::from_reader(BufReader::new(file))
+        .context(format!("failed to read EPUB: {:?}", path))
+}
+
+fn run_extract(args: ExtractArgs) -> CliResult<()> {
+    let book = read_epub(&args.epub)?;
+    let path = Path::new(&args.epub);
+
+    let dir = match args.directory {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(path.file_stem().ok_or_else(|| {
+            CliError::from(format!(
+                "failed to get file stem for EPUB: {:?}",
+                path.display()
+            ))
+        })?),
+    };
+    create_dir_all(&dir).context(format!("failed to create directory: {:?}", dir.display()))?;
+
+    let spoiler_mode = book.spoiler_mode();
+
+    // The book is built again next to the extracted files, so the original isn't replaced.
+    let mut manifest = Manifest::from_book(&book, path.file_name().unwrap_or_default());
+    manifest.theme = book.theme().and_then(theme_arg);
+    manifest.green_color = book.green_color();
+    manifest.spoiler_color = book.spoiler_color();
+    manifest.orange_color = book.orange_color();
+    manifest.caret_color = book.caret_color();
+    manifest.spoiler_mode = Some(spoiler_rendering(spoiler_mode));
+
+    if let Some(cover) = &book.cover {
+        let file = PathBuf::from(format!("cover.{}", cover.extension()));
+        let cover_path = dir.join(&file);
+
+        debug!("Writing cover to {:?}", cover_path.display());
+        write(&cover_path, cover.bytes()).context(format!(
+            "failed to write cover image: {:?}",
+            cover_path.display()
+        ))?;
+        manifest.cover = Some(file);
+    }
+
+    for chapter in &book.chapters {
+        let file = PathBuf::from(format!("{:03}.txt", chapter.number));
+        let chapter_path = dir.join(&file);
+
+        debug!(
+            "Writing chapter {:?} to {:?}",
+            chapter.title,
+            chapter_path.display()
+        );
+        write(&chapter_path, chapter.text(spoiler_mode)).context(format!(
+            "failed to write chapter: {:?}",
+            chapter_path.display()
+        ))?;
+        manifest.chapters.push(ManifestChapter {
+            file,
+            title: Some(chapter.title.clone()),
+        });
+    }
+
+    let manifest_path = dir.join(MANIFEST_FILE);
+    write(&manifest_path, manifest.to_toml()?).context(format!(
+        "failed to write manifest: {:?}",
+        manifest_path.display()
+    ))?;
+
+    info!(
+        "{}",
+        style(format_args!(
+            "Extracted {} chapter{} to {:?}, build the book again with `green2epub build {}`",
+            book.chapters.len(),
+            if book.chapters.len() == 1 { "" } else { "s" },
+            style(dir.display()).bold(),
+            manifest_path.display()
+        ))
+        .green()
+    );
+
+    Ok(())
+}
+
+fn run_check(args: CheckArgs) -> CliResult<()> {
+    let spoilers: Vec<SpoilerSyntax> = args
+        .spoiler_syntaxes
+        .into_iter()
+        .map(spoiler_syntax)
+        .collect();
+
+    let mut report: Vec<(String, Diagnostic)> = Vec::new();
+    for path in &args.files {
+        debug!("Checking file {:?}", path);
+        let bytes = read(path).context(format!("failed to read input file: {:?}", path))?;
+
+        report.extend(
+            check(&bytes, &spoilers)
+                .into_iter()
+                .map(|diagnostic| (path.clone(), diagnostic)),
+        );
+    }
+
+    match args.format {
+        ReportFormat::Human => {
+            for (path, diagnostic) in &report {
+                println!(
+                    "{}:{}: {} [{}]",
+                    path,
+                    diagnostic.position,
+                    diagnostic.problem,
+                    diagnostic.problem.code()
+                );
+            }
+        }
+        ReportFormat::Json => {
+            let problems: Vec<_> = report
+                .iter()
+                .map(|(path, diagnostic)| {
+                    json!({
+                        "file": path,
+                        "line": diagnostic.position.line,
+                        "column": diagnostic.position.column,
+                        "code": diagnostic.problem.code(),
+                        "message": diagnostic.problem.to_string(),
+                    })
+                })
+                .collect();
+            println!("{}", json!(problems));
+        }
+    };
+
+    if !report.is_empty() {
+        return Err(CliError::from(format!(
+            "found {} problem{}",
+            report.len(),
+            if report.len() > 1 { "s" } else { "" }
+        )));
+    }
+
+    info!("{}", style("No problems found").green());
+
+    Ok(())
+}
+
+/// Builds the book described by the manifest, after the chapters of an existing one if given.
+fn build(mut manifest: Manifest, existing: Option<ExistingBook>) -> CliResult<()> {
+    let title = manifest.title.ok_or_else(|| {
+        CliError::from(String::from(
+            "the book needs a title, set it with `--title` or `title` in the manifest",
+        ))
+    })?;
+    if manifest.authors.is_empty() {
+        return Err(CliError::from(String::from(
+            "the book needs an author, set it with `--author` or `authors` in the manifest",
+        )));
+    }
+    let output = manifest.output.ok_or_else(|| {
+        CliError::from(String::from(
+            "the book needs an output path, set it with `--output` or `output` in the manifest",
+        ))
+    })?;
+
+    let mut builder = BookBuilder::new(title);
+
+    // The look of an existing book is kept unless new colors, stylesheets or a spoiler mode,
+    // which has its own rules, are given for it.
+    let restyled = manifest.theme.is_some()
+        || manifest.spoiler_mode.is_some()
+        || manifest.green_color.is_some()
+        || manifest.spoiler_color.is_some()
+        || manifest.orange_color.is_some()
+        || manifest.caret_color.is_some()
+        || !manifest.stylesheets.is_empty()
+        || !manifest.css_vars.is_empty();
+
+    // New chapters are parsed the same way as the ones of the book, unless told otherwise.
+    if let Some(existing) = &existing {
+        manifest.spoiler_mode = manifest
+            .spoiler_mode
+            .or_else(|| Some(spoiler_rendering(existing.spoiler_mode())));
+        manifest.theme = manifest
+            .theme
+            .or_else(|| existing.theme().and_then(theme_arg));
+        manifest.orange_color = manifest
+            .orange_color
+            .take()
+            .or_else(|| existing.orange_color());
+        manifest.caret_color = manifest
+            .caret_color
+            .take()
+            .or_else(|| existing.caret_color());
+    }
+
+    if manifest.author_file_as.len() > manifest.authors.len() {
+        return Err(CliError::from(format!(
+            "got {} sort names for {} author{}, each one is for the author in the same position",
+            manifest.author_file_as.len(),
+            manifest.authors.len(),
+            if manifest.authors.len() == 1 { "" } else { "s" }
+        )));
+    }
+
+    let mut file_as = manifest.author_file_as.into_iter();
+    for author in manifest.authors {
+        let mut author = Contributor::new(author, Role::Author);
+        if let Some(name) = file_as.next() {
+            author = author.with_file_as(name);
+        }
+        builder.contributor(author);
+    }
+
+    for (role, names) in [
+        (Role::Editor, manifest.editors),
+        (Role::Translator, manifest.translators),
+        (Role::Illustrator, manifest.illustrators),
+    ] {
+        for name in names {
+            builder.contributor(Contributor::new(name, role));
+        }
+    }
+
+    if let Some(description) = manifest.description {
+        builder.description(description);
+    }
+
+    if let Some(publisher) = manifest.publisher {
+        builder.publisher(publisher);
+    }
+
+    if let Some(language) = manifest.language {
+        builder.language(language);
+    }
+
+    if let Some(date) = manifest.date {
+        builder.date(date);
+    }
+
+    if let Some(rights) = manifest.rights {
+        builder.rights(rights);
+    }
+
+    if let Some(source) = manifest.source {
+        builder.source(source);
+    }
+
+    if let Some(identifier) = manifest.identifier {
+        builder.identifier(identifier);
+    }
+
+    match (manifest.series, manifest.series_index) {
+        (Some(series), index) => {
+            builder.series(series);
+            if let Some(index) = index {
+                builder.series_index(index);
+            }
+        }
+        (None, Some(_)) => {
+            return Err(CliError::from(String::from(
+                "a series index needs a series, set it with `--series` or `series` in the manifest",
+            )))
+        }
+        (None, None) => {}
+    }
+
+    if let Some(theme) = manifest.theme {
+        builder.theme(match theme {
+            Theme::Default => content::Theme::Default,
+            Theme::Yotsuba => content::Theme::Yotsuba,
+            Theme::Dark => content::Theme::Dark,
+            Theme::Eink => content::Theme::Eink,
+        });
+    }
+
+    if let Some(color) = manifest.green_color {
+        builder.green_color(color);
+    }
+
+    if let Some(color) = manifest.spoiler_color {
+        builder.spoiler_color(color);
+    }
+
+    if let Some(color) = manifest.orange_color {
+        builder.highlight(Highlight::orangetext(color));
+    }
+
+    if let Some(color) = manifest.caret_color {
+        builder.highlight(Highlight::caret(color));
+    }
+
+    for path in manifest.stylesheets {
+        info!("Adding stylesheet {:?}", style(path.display()).bold());
+        let css = read_to_string(&path)
+            .context(format!("failed to read stylesheet: {:?}", path.display()))?;
+        let mut stylesheet = UserStylesheet::new(css)
+            .context(format!("failed to parse stylesheet: {:?}", path.display()))?;
+
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+        for file in stylesheet
+            .files()
+            .into_iter()
+            .map(String::from)
+            .collect::<Vec<String>>()
+        {
+            debug!("Packing {:?} referred to by the stylesheet", file);
+            let bytes = read(dir.join(&file)).context(format!(
+                "failed to read file referred to by stylesheet {:?}: {:?}",
+                path.display(),
+                file
+            ))?;
+            stylesheet.resource(file, bytes)?;
+        }
+
+        builder.user_stylesheet(stylesheet);
+    }
+
+    for (name, value) in manifest.css_vars {
+        builder.css_variable(name, value);
+    }
+
+    for syntax in manifest.spoiler_syntaxes {
+        builder.spoiler_syntax(spoiler_syntax(syntax));
+    }
+
+    if let Some(mode) = manifest.spoiler_mode {
+        builder.spoiler_mode(match mode {
+            SpoilerRendering::Blackout => SpoilerMode::Blackout,
+            SpoilerRendering::Footnote => SpoilerMode::Footnote,
+            SpoilerRendering::Endnote => SpoilerMode::Endnote,
+            SpoilerRendering::Inline => SpoilerMode::Inline,
+        });
+    }
+
+    builder.post_headers(!manifest.no_post_headers);
 
-    let mut epub = EpubBuilder::new(ZipLibrary::new()?)?;
-    epub.epub_version(EpubVersion::V30)
-        .metadata("author", args.author)?
-        .metadata("title", args.title)?
-        .stylesheet(stylesheet_content(args.green_color, args.spoiler_color).as_slice())?;
+    let source_date = source_date_epoch()?;
+    if manifest.reproducible || source_date.is_some() {
+        builder.reproducible(
+            source_date.unwrap_or(UNIX_EPOCH + Duration::from_secs(REPRODUCIBLE_DATE)),
+        );
+    }
 
-    for subject in args.subjects {
-        epub.metadata("subject", subject)?;
+    for subject in manifest.subjects {
+        builder.subject(subject);
     }
 
-    if let Some(path) = args.cover.map(PathBuf::from) {
+    let normalization = (manifest.normalize_cover
+        || manifest.cover_format.is_some()
+        || manifest.cover_max_size.is_some()
+        || manifest.cover_quality.is_some())
+    .then(|| {
+        let default = cover::CoverNormalization::default();
+        cover::CoverNormalization {
+            format: match manifest.cover_format {
+                Some(CoverFormat::Png) => cover::CoverFormat::Png,
+                Some(CoverFormat::Jpeg) | None => cover::CoverFormat::Jpeg,
+            },
+            max_size: manifest.cover_max_size.unwrap_or(default.max_size),
+            quality: manifest.cover_quality.unwrap_or(default.quality),
+        }
+    });
+    let normalize = |cover: Cover| match &normalization {
+        Some(normalization) => cover.normalize(normalization),
+        None => Ok(cover),
+    };
+
+    let has_cover = manifest.cover.is_some();
+    if let Some(path) = manifest.cover {
         info!("Setting cover to {:?}", style(path.display()).bold());
 
         debug!("Opening cover file");
-        let mut image_bytes: Vec<u8> = Vec::new();
-        File::open(&path)
-            .and_then(|mut file| file.read_to_end(&mut image_bytes))
-            .context(format!("failed to open cover image: {:?}", path.display()))?;
-
-        let (extension, mime_type, dimensions) = match (
-            imagesize::image_type(&image_bytes),
-            imagesize::blob_size(&image_bytes),
-        ) {
-            (Ok(img_type), Ok(img_size)) => {
-                use imagesize::ImageType;
-
-                let dimensions = (img_size.width, img_size.height);
-                let (extension, mime_type) = match img_type {
-                    ImageType::Bmp => ("bmp", "image/bmp"),
-                    ImageType::Gif => ("gif", "image/gif"),
-                    ImageType::Jpeg => ("jpg", "image/jpeg"),
-                    ImageType::Png => ("png", "image/png"),
-                    ImageType::Webp => ("webp", "image/webp"),
-                    _ => {
-                        return Err(CliError::from(format!(
-                            "invalid format for cover image: {:?}",
-                            img_type
-                        )))
-                    }
-                };
-
-                debug!("Cover image format: {:?}", extension);
-                debug!("Cover image size: {:?}", dimensions);
-
-                (extension, mime_type, dimensions)
+        let image_bytes =
+            read(&path).context(format!("failed to open cover image: {:?}", path.display()))?;
+
+        builder.cover(normalize(Cover::new(image_bytes)?)?);
+    }
+
+    if !manifest.no_generated_cover {
+        builder.generate_cover(
+            match manifest.cover_layout.unwrap_or(CoverLayout::Centered) {
+                CoverLayout::Centered => cover::CoverLayout::Centered,
+                CoverLayout::Band => cover::CoverLayout::Band,
+                CoverLayout::Corner => cover::CoverLayout::Corner,
+            },
+            match manifest.cover_colors.unwrap_or(CoverColors::Greentext) {
+                CoverColors::Greentext => cover::CoverColors::Greentext,
+                CoverColors::Light => cover::CoverColors::Light,
+                CoverColors::Dark => cover::CoverColors::Dark,
+                CoverColors::Yotsuba => cover::CoverColors::Yotsuba,
+            },
+        );
+    }
+
+    if let Some(existing) = existing {
+        if !restyled {
+            // Generated covers are drawn with the green color of the book.
+            if let Some(color) = existing.green_color() {
+                builder.green_color(color);
             }
-            (Err(err), _) => {
-                return Err(CliError::from(err).context(format!(
-                    "failed to recognize cover image format: {:?}",
-                    path.display()
-                )))
+            if let Some(stylesheet) = existing.stylesheet {
+                builder.stylesheet(stylesheet);
             }
-            (_, Err(err)) => {
-                return Err(CliError::from(err).context(format!(
-                    "failed to get cover image dimensions: {:?}",
-                    path.display()
-                )))
+            for (href, bytes, mime_type) in existing.resources {
+                builder.resource(href, bytes, mime_type);
             }
-        };
+        }
 
-        let href = format!("img/cover.{}", extension);
+        if let (false, Some(cover)) = (has_cover, existing.cover) {
+            builder.cover(normalize(cover)?);
+        }
 
-        debug!("Adding cover resources to EPUB");
-        epub.add_cover_image(&href, image_bytes.as_slice(), mime_type)?;
-        epub.add_resource(
-            COVER_STYLESHEET,
-            include_bytes!(concat!(
-                env!("CARGO_MANIFEST_DIR"),
-                "/static/coverstyle.css"
-            ))
-            .as_slice(),
-            "text/css",
-        )?;
-        epub.add_content(
-            EpubContent::new(
-                "content/cover.xhtml",
-                coverpage_content(&href, dimensions).as_bytes(),
-            )
-            .title("Cover")
-            .reftype(ReferenceType::Cover),
-        )?;
+        info!("Keeping {} chapters of the book", existing.chapters.len());
+        for chapter in existing.chapters {
+            builder.chapter(chapter.into_chapter());
+        }
     }
 
-    // NOTE: Keep TOC after the cover page.
-    epub.inline_toc();
+    let title_regex = match manifest.chapter_title_regex {
+        Some(regex) => Some(
+            Regex::new(&regex)
+                .map_err(|err| CliError::from(err.to_string()))
+                .context("invalid regular expression for chapter titles")?,
+        ),
+        None => None,
+    };
+
+    let post_filter = if manifest.posts.is_empty() {
+        match manifest.op_match.unwrap_or(OpMatch::Auto) {
+            OpMatch::Auto => PostFilter::Op,
+            OpMatch::Name => PostFilter::OpName,
+            OpMatch::Trip => PostFilter::OpTrip,
+            OpMatch::Id => PostFilter::OpId,
+        }
+    } else {
+        PostFilter::Numbers(manifest.posts)
+    };
 
-    for (count, path) in args
-        .files
+    if manifest
+        .chapters
         .iter()
-        .enumerate()
-        .map(|(i, path)| (i + 1, PathBuf::from(path)))
+        .filter(|chapter| is_stdin(&chapter.file))
+        .count()
+        > 1
     {
-        let title = match path.file_stem() {
+        return Err(CliError::from(String::from(
+            "standard input can only be read once, but `-` is given more than once",
+        )));
+    }
+
+    debug!("Chapter order:");
+    for (idx, chapter) in manifest.chapters.iter().enumerate() {
+        debug!("{}. {:?}", idx + 1, chapter.file.display());
+    }
+
+    for chapter in manifest.chapters {
+        let path = chapter.file;
+        let from_stdin = is_stdin(&path);
+
+        let stem = match path.file_stem() {
+            _ if from_stdin => Cow::Borrowed(STDIN_TITLE),
             Some(stem) => stem.to_string_lossy(),
             None => {
                 return Err(CliError::from(format!(
@@ -132,79 +600,124 @@ fn run(args: Args) -> CliResult<()> {
                 )))
             }
         };
-        let mut paste = PasteContent::new(&title);
-
-        debug!("Opening file {:?}", path.display());
-        let content = read_to_string(&path)
-            .context(format!("failed to read input file: {:?}", path.display()))?;
-
-        let mut line_parser = LineParser::default();
-        macro_rules! parse_content {
-            ($iter:expr) => {
-                for line in $iter {
-                    if line.is_empty() {
-                        paste.add_line(Tag::new("br"));
-                        continue;
-                    }
-
-                    paste.add_line(line_parser.parse(line));
-                }
-            };
-        }
 
-        if log_enabled!(Level::Info) {
-            let progress = ProgressBar::new(content.lines().count() as u64)
-                .with_message(format!("Parsing {:?}", style(path.display()).bold()))
-                .with_style(
-                    ProgressStyle::default_spinner()
-                        .template("  {spinner}  {msg} {percent:>3}%")
-                        .on_finish(ProgressFinish::AndClear),
-                );
+        let content = if from_stdin {
+            debug!("Reading standard input");
+            let mut content = String::new();
+            io::stdin()
+                .read_to_string(&mut content)
+                .context("failed to read standard input")?;
+            content
+        } else {
+            debug!("Opening file {:?}", path.display());
+            read_to_string(&path)
+                .context(format!("failed to read input file: {:?}", path.display()))?
+        };
+
+        let mut book_chapter = match input_format(manifest.input_format, &path) {
+            InputFormat::FourchanJson => {
+                let thread = fourchan::Thread::from_json(&content)
+                    .context(format!("failed to read thread: {:?}", path.display()))?;
+                let title = thread.subject().unwrap_or_else(|| stem.to_string());
+
+                Chapter::from_posts(title, thread.posts(&post_filter))
+            }
+            InputFormat::ArchiveHtml => {
+                let thread = archive::Thread::from_html(&content).context(format!(
+                    "failed to read archived thread: {:?}",
+                    path.display()
+                ))?;
+                let title = thread
+                    .subject()
+                    .map(String::from)
+                    .unwrap_or_else(|| stem.to_string());
 
-            parse_content!(content.lines().progress_with(progress));
+                Chapter::from_posts(title, thread.posts(&post_filter))
+            }
+            _ => Chapter::new(&stem, content),
+        }
+        .with_source(if from_stdin {
+            STDIN_SOURCE.to_string()
         } else {
-            parse_content!(content.lines());
+            path.display().to_string()
+        });
+
+        let file_name = path.file_name().filter(|_| !from_stdin);
+        if let (Some(regex), Some(name)) = (&title_regex, file_name) {
+            match title_from_file_name(regex, &name.to_string_lossy()) {
+                Some(title) => book_chapter = book_chapter.with_title(title),
+                None => warn!(
+                    "File name doesn't match the regular expression for chapter titles: {:?}",
+                    path.display()
+                ),
+            };
         }
 
-        info!("Parsed {:?}", style(path.display()).bold());
+        if manifest.chapter_title_first_line {
+            book_chapter = book_chapter.with_title_from_first_line();
+        }
+        if let (true, Some(title)) = (from_stdin, &manifest.stdin_title) {
+            book_chapter = book_chapter.with_title(title);
+        }
+        if let Some(title) = chapter.title {
+            book_chapter = book_chapter.with_title(title);
+        }
 
-        if line_parser.is_spoiler_open() {
-            warn!(
-                "Input file has a spoiler that hasn't been closed and extended to the end of the file: {:?}",
-                style(path.display()).bold(),
+        builder.chapter(book_chapter);
+    }
+
+    let mut progress_bar: Option<ProgressBar> = None;
+    let mut book = builder.build_with_progress(|progress| match progress {
+        Progress::Chapter { source, lines } if log_enabled!(Level::Info) => {
+            progress_bar = Some(
+                ProgressBar::new(lines as u64)
+                    .with_message(format!("Parsing {:?}", style(source).bold()))
+                    .with_style(
+                        ProgressStyle::default_spinner()
+                            .template("  {spinner}  {msg} {percent:>3}%")
+                            .on_finish(ProgressFinish::AndClear),
+                    ),
             );
         }
+        Progress::Line => {
+            if let Some(progress_bar) = &progress_bar {
+                progress_bar.inc(1);
+            }
+        }
+        Progress::Parsed => {
+            if let Some(progress_bar) = progress_bar.take() {
+                progress_bar.finish_using_style();
+            }
+        }
+        Progress::Chapter { .. } => {}
+    })?;
 
-        debug!(
-            "Adding parsed content of {:?} to EPUB with title {:?}",
-            path.display(),
-            &title
-        );
-        epub.add_content(
-            EpubContent::new(
-                format!("content/paste-{:03}.xhtml", count),
-                paste.build().as_bytes(),
-            )
-            .title(title),
-        )?;
-    }
+    // Generated before the output file is created, so a book that is rewritten in place isn't
+    // lost if it fails.
+    let mut bytes: Vec<u8> = Vec::new();
+    book.generate(&mut bytes)?;
 
     debug!("Creating output file");
     let mut output_file = OpenOptions::new()
         .create(true)
         .truncate(true)
         .write(true)
-        .open(&args.output)
-        .context(format!("failed to create output file: {:?}", &args.output))?;
+        .open(&output)
+        .context(format!(
+            "failed to create output file: {:?}",
+            output.display()
+        ))?;
 
-    epub.generate(&mut output_file)
-        .context("failed to generate EPUB")?;
+    output_file.write_all(&bytes).context(format!(
+        "failed to write output file: {:?}",
+        output.display()
+    ))?;
 
     info!(
         "{}",
         style(format_args!(
             "Successfully generated {:?}",
-            style(args.output).bold()
+            style(output.display()).bold()
         ))
         .green()
     );
@@ -212,6 +725,49 @@ fn run(args: Args) -> CliResult<()> {
     Ok(())
 }
 
+fn run(args: Args) -> CliResult<()> {
+    logger::init(args.verbose, args.quiet, args.color)?;
+
+    debug!("Parsed arguments: {:?}", args);
+
+    let (mut manifest, book_args, files, existing) = match args.command {
+        Some(Command::Check(check_args)) => return run_check(check_args),
+        Some(Command::Build(build_args)) => (
+            Manifest::from_file(&build_args.manifest)?,
+            build_args.book,
+            args.files,
+            None,
+        ),
+        Some(Command::Extract(extract_args)) => return run_extract(extract_args),
+        Some(Command::Append(append_args)) => {
+            let existing = read_epub(&append_args.epub)?;
+            (
+                Manifest::from_book(&existing, &append_args.epub),
+                append_args.book,
+                append_args.files,
+                Some(existing),
+            )
+        }
+        None => (Manifest::default(), BookArgs::default(), args.files, None),
+    };
+
+    // Chapters are expanded before the flags are merged, so they can title them by number.
+    let order = args
+        .book
+        .sort
+        .or(book_args.sort)
+        .or(manifest.sort)
+        .unwrap_or(SortOrder::Natural);
+    manifest
+        .files(files)
+        .expand(order)?
+        .merge(book_args)?
+        .merge(args.book)?
+        .load_metadata()?;
+
+    build(manifest, existing)
+}
+
 fn main() {
     let args = Args::parse();
 
@@ -220,3 +776,60 @@ fn main() {
         std::process::exit(1)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::fs::remove_dir_all;
+
+    #[test]
+    fn append_keeps_parsing() {
+        let dir = std::env::temp_dir().join(format!("green2epub-append-{}", std::process::id()));
+        create_dir_all(&dir).unwrap();
+        let epub = dir.join("book.epub");
+        for (name, text) in [
+            ("001.txt", "<orange [spoiler]secret[/spoiler]"),
+            ("002.txt", "<orange2 [spoiler]secret2[/spoiler]"),
+        ] {
+            write(dir.join(name), text).unwrap();
+        }
+
+        let mut manifest = Manifest {
+            title: Some("Paste".into()),
+            authors: vec!["Author".into()],
+            output: Some(epub.clone()),
+            spoiler_mode: Some(SpoilerRendering::Footnote),
+            orange_color: Some("#ff8800".into()),
+            ..Manifest::default()
+        };
+        manifest.files([dir.join("001.txt").display().to_string()]);
+        build(manifest, None).unwrap();
+
+        let existing = read_epub(&epub.display().to_string()).unwrap();
+        let mut manifest = Manifest::from_book(&existing, &epub);
+        manifest.files([dir.join("002.txt").display().to_string()]);
+        build(manifest, Some(existing)).unwrap();
+
+        let book = read_epub(&epub.display().to_string()).unwrap();
+        assert_eq!(book.spoiler_mode(), SpoilerMode::Footnote);
+        assert_eq!(book.orange_color().as_deref(), Some("#ff8800"));
+        let xhtml = &book.chapters[1].xhtml;
+        assert!(xhtml.contains(r#"<p class="orangetext">&lt;orange2 "#));
+        assert!(xhtml.contains(r#"epub:type="noteref""#));
+
+        // A new spoiler mode gets the rules for it.
+        let existing = read_epub(&epub.display().to_string()).unwrap();
+        let mut manifest = Manifest::from_book(&existing, &epub);
+        manifest.spoiler_mode = Some(SpoilerRendering::Blackout);
+        manifest.files([dir.join("002.txt").display().to_string()]);
+        build(manifest, Some(existing)).unwrap();
+
+        let book = read_epub(&epub.display().to_string()).unwrap();
+        assert_eq!(book.spoiler_color().as_deref(), Some("#000"));
+        assert_eq!(book.orange_color().as_deref(), Some("#ff8800"));
+        assert!(book.chapters[2].xhtml.contains("<span>secret2</span>"));
+
+        remove_dir_all(&dir).unwrap();
+    }
+}
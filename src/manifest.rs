@@ -0,0 +1,839 @@
+use std::{
+    collections::BTreeMap,
+    fs::read_to_string,
+    path::{Path, PathBuf},
+};
+
+use clap::ArgEnum;
+use green2epub::{epub::ExistingBook, CliError, CliResult, ResultExt, Role};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::args::{
+    cover_quality_validator, date_validator, hex_color_validator, parse_cover_size, parse_css_var,
+    series_index_validator, BookArgs, CoverColors, CoverFormat, CoverLayout, InputFormat, OpMatch,
+    SortOrder, Spoiler, SpoilerRendering, Theme,
+};
+use crate::inputs;
+
+/// File name that stands for standard input.
+pub const STDIN_FILE: &str = "-";
+
+/// Tells if the path stands for standard input instead of a file.
+pub fn is_stdin(path: &Path) -> bool {
+    path == Path::new(STDIN_FILE)
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum OneOrMany {
+    One(String),
+    Many(Vec<String>),
+}
+
+fn one_or_many<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(value) => vec![value],
+        OneOrMany::Many(values) => values,
+    })
+}
+
+fn color<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let color = String::deserialize(deserializer)?;
+    hex_color_validator(&color).map_err(de::Error::custom)?;
+    Ok(Some(color))
+}
+
+fn date<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let date = String::deserialize(deserializer)?;
+    date_validator(&date).map_err(de::Error::custom)?;
+    Ok(Some(date))
+}
+
+fn series_index<'de, D>(deserializer: D) -> Result<Option<f64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let index = f64::deserialize(deserializer)?;
+    series_index_validator(&index.to_string()).map_err(de::Error::custom)?;
+    Ok(Some(index))
+}
+
+fn cover_size<'de, D>(deserializer: D) -> Result<Option<(u32, u32)>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let size = String::deserialize(deserializer)?;
+    parse_cover_size(&size).map(Some).map_err(de::Error::custom)
+}
+
+fn cover_size_name<S>(size: &Option<(u32, u32)>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    match size {
+        Some((width, height)) => serializer.serialize_str(&format!("{}x{}", width, height)),
+        None => serializer.serialize_none(),
+    }
+}
+
+fn cover_quality<'de, D>(deserializer: D) -> Result<Option<u8>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let quality = u64::deserialize(deserializer)?;
+    cover_quality_validator(&quality.to_string()).map_err(de::Error::custom)?;
+    Ok(Some(quality as u8))
+}
+
+fn css_vars<'de, D>(deserializer: D) -> Result<BTreeMap<String, String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    BTreeMap::<String, String>::deserialize(deserializer)?
+        .into_iter()
+        .map(|(name, value)| parse_css_var(&format!("{}={}", name, value)))
+        .collect::<Result<_, _>>()
+        .map_err(de::Error::custom)
+}
+
+/// Deserializes a value with the same name it has as a flag value.
+fn arg_enum<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: ArgEnum,
+{
+    let value = String::deserialize(deserializer)?;
+    T::from_str(&value, false).map_err(de::Error::custom)
+}
+
+fn spoiler_syntaxes<'de, D>(deserializer: D) -> Result<Vec<Spoiler>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Vec::<String>::deserialize(deserializer)?
+        .iter()
+        .map(|value| Spoiler::from_str(value, false).map_err(de::Error::custom))
+        .collect()
+}
+
+fn some_arg_enum<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: ArgEnum,
+{
+    arg_enum(deserializer).map(Some)
+}
+
+/// Name a value has as a flag value.
+fn arg_enum_name<T>(value: &T) -> &'static str
+where
+    T: ArgEnum,
+{
+    value
+        .to_possible_value()
+        .expect("values aren't skipped")
+        .get_name()
+}
+
+fn some_arg_enum_name<S, T>(value: &Option<T>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    T: ArgEnum,
+{
+    value.as_ref().map(arg_enum_name).serialize(serializer)
+}
+
+fn arg_enum_names<S, T>(values: &[T], serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    T: ArgEnum,
+{
+    serializer.collect_seq(values.iter().map(arg_enum_name))
+}
+
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(untagged)]
+enum ChapterEntry {
+    File(PathBuf),
+    Table {
+        file: PathBuf,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        title: Option<String>,
+    },
+}
+
+/// A chapter of the book, written either as the path of its file or as a table that can also
+/// set its title.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(from = "ChapterEntry", into = "ChapterEntry")]
+pub struct ManifestChapter {
+    pub file: PathBuf,
+    pub title: Option<String>,
+}
+
+impl From<ChapterEntry> for ManifestChapter {
+    fn from(entry: ChapterEntry) -> Self {
+        match entry {
+            ChapterEntry::File(file) => Self { file, title: None },
+            ChapterEntry::Table { file, title } => Self { file, title },
+        }
+    }
+}
+
+impl From<ManifestChapter> for ChapterEntry {
+    fn from(chapter: ManifestChapter) -> Self {
+        match chapter.title {
+            Some(title) => ChapterEntry::Table {
+                file: chapter.file,
+                title: Some(title),
+            },
+            None => ChapterEntry::File(chapter.file),
+        }
+    }
+}
+
+/// Description of a whole book, with the same options that can be given as flags.
+#[derive(Deserialize, Serialize, Debug, Default)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Manifest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(
+        alias = "author",
+        deserialize_with = "one_or_many",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub authors: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cover: Option<PathBuf>,
+    #[serde(
+        deserialize_with = "some_arg_enum",
+        serialize_with = "some_arg_enum_name",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub cover_layout: Option<CoverLayout>,
+    #[serde(
+        deserialize_with = "some_arg_enum",
+        serialize_with = "some_arg_enum_name",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub cover_colors: Option<CoverColors>,
+    #[serde(skip_serializing_if = "is_false")]
+    pub no_generated_cover: bool,
+    #[serde(skip_serializing_if = "is_false")]
+    pub normalize_cover: bool,
+    #[serde(
+        deserialize_with = "some_arg_enum",
+        serialize_with = "some_arg_enum_name",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub cover_format: Option<CoverFormat>,
+    #[serde(
+        deserialize_with = "cover_size",
+        serialize_with = "cover_size_name",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub cover_max_size: Option<(u32, u32)>,
+    #[serde(
+        deserialize_with = "cover_quality",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub cover_quality: Option<u8>,
+    #[serde(alias = "tags", skip_serializing_if = "Vec::is_empty")]
+    pub subjects: Vec<String>,
+    #[serde(
+        deserialize_with = "one_or_many",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub author_file_as: Vec<String>,
+    #[serde(
+        alias = "editor",
+        deserialize_with = "one_or_many",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub editors: Vec<String>,
+    #[serde(
+        alias = "translator",
+        deserialize_with = "one_or_many",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub translators: Vec<String>,
+    #[serde(
+        alias = "illustrator",
+        deserialize_with = "one_or_many",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub illustrators: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub publisher: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    #[serde(deserialize_with = "date", skip_serializing_if = "Option::is_none")]
+    pub date: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rights: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub identifier: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub series: Option<String>,
+    #[serde(
+        deserialize_with = "series_index",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub series_index: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output: Option<PathBuf>,
+    #[serde(
+        deserialize_with = "some_arg_enum",
+        serialize_with = "some_arg_enum_name",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub theme: Option<Theme>,
+    #[serde(deserialize_with = "color", skip_serializing_if = "Option::is_none")]
+    pub green_color: Option<String>,
+    #[serde(deserialize_with = "color", skip_serializing_if = "Option::is_none")]
+    pub spoiler_color: Option<String>,
+    #[serde(deserialize_with = "color", skip_serializing_if = "Option::is_none")]
+    pub orange_color: Option<String>,
+    #[serde(deserialize_with = "color", skip_serializing_if = "Option::is_none")]
+    pub caret_color: Option<String>,
+    #[serde(
+        deserialize_with = "spoiler_syntaxes",
+        serialize_with = "arg_enum_names",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub spoiler_syntaxes: Vec<Spoiler>,
+    #[serde(
+        deserialize_with = "some_arg_enum",
+        serialize_with = "some_arg_enum_name",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub spoiler_mode: Option<SpoilerRendering>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub stylesheets: Vec<PathBuf>,
+    #[serde(
+        deserialize_with = "css_vars",
+        skip_serializing_if = "BTreeMap::is_empty"
+    )]
+    pub css_vars: BTreeMap<String, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chapter_title_regex: Option<String>,
+    #[serde(skip_serializing_if = "is_false")]
+    pub chapter_title_first_line: bool,
+    #[serde(
+        deserialize_with = "some_arg_enum",
+        serialize_with = "some_arg_enum_name",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub input_format: Option<InputFormat>,
+    #[serde(
+        deserialize_with = "some_arg_enum",
+        serialize_with = "some_arg_enum_name",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub op_match: Option<OpMatch>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub posts: Vec<u64>,
+    #[serde(skip_serializing_if = "is_false")]
+    pub no_post_headers: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stdin_title: Option<String>,
+    #[serde(
+        deserialize_with = "some_arg_enum",
+        serialize_with = "some_arg_enum_name",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub sort: Option<SortOrder>,
+    #[serde(skip_serializing_if = "is_false")]
+    pub reproducible: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub chapters: Vec<ManifestChapter>,
+}
+
+impl Manifest {
+    /// Reads a manifest in JSON format if the file has a `json` extension and in TOML format
+    /// otherwise.
+    pub fn from_file<P>(path: P) -> CliResult<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+
+        debug!("Opening manifest {:?}", path.display());
+        let content = read_to_string(path)
+            .context(format!("failed to read manifest: {:?}", path.display()))?;
+
+        let is_json = path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("json"));
+        let parsed = if is_json {
+            serde_json::from_str::<Manifest>(&content).map_err(|err| err.to_string())
+        } else {
+            toml::from_str::<Manifest>(&content).map_err(|err| err.to_string())
+        };
+        let mut manifest = parsed
+            .map_err(CliError::from)
+            .context(format!("failed to parse manifest: {:?}", path.display()))?;
+
+        if let Some(dir) = path.parent() {
+            manifest.relative_to(dir);
+        }
+
+        Ok(manifest)
+    }
+
+    /// Manifest with the metadata of a book that was already built, written back to the same
+    /// path.
+    pub fn from_book<P>(book: &ExistingBook, path: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        let metadata = &book.metadata;
+        let names = |role: Role| -> Vec<String> {
+            metadata
+                .contributors
+                .iter()
+                .filter(|contributor| contributor.role == role)
+                .map(|contributor| contributor.name.clone())
+                .collect()
+        };
+
+        // Sort names are given by position, so authors without one are sorted by their name.
+        let author_file_as = if metadata.authors().any(|author| author.file_as.is_some()) {
+            metadata
+                .authors()
+                .map(|author| author.file_as.as_ref().unwrap_or(&author.name).clone())
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        Self {
+            title: Some(metadata.title.clone()),
+            authors: names(Role::Author),
+            subjects: metadata.subjects.clone(),
+            author_file_as,
+            editors: names(Role::Editor),
+            translators: names(Role::Translator),
+            illustrators: names(Role::Illustrator),
+            description: metadata.description.clone(),
+            publisher: metadata.publisher.clone(),
+            language: metadata.language.clone(),
+            date: metadata.date.clone(),
+            rights: metadata.rights.clone(),
+            source: metadata.source.clone(),
+            identifier: metadata.identifier.clone(),
+            series: metadata.series.clone(),
+            series_index: metadata.series_index,
+            output: Some(path.into()),
+            ..Self::default()
+        }
+    }
+
+    /// Writes the manifest in TOML format, leaving out what isn't set.
+    pub fn to_toml(&self) -> CliResult<String> {
+        toml::to_string_pretty(self)
+            .map_err(|err| CliError::from(err.to_string()))
+            .context("failed to write manifest")
+    }
+
+    /// Makes relative paths start from the given directory, leaving standard input as is.
+    fn relative_to(&mut self, dir: &Path) {
+        for path in self
+            .cover
+            .iter_mut()
+            .chain(self.output.iter_mut())
+            .chain(self.metadata.iter_mut())
+            .chain(self.stylesheets.iter_mut())
+            .chain(self.chapters.iter_mut().map(|chapter| &mut chapter.file))
+            .filter(|path| !is_stdin(path))
+        {
+            *path = dir.join(&path);
+        }
+    }
+
+    /// Overrides the values of the manifest with the ones given as flags.
+    ///
+    /// Fails if a chapter title is given for a chapter that isn't in the manifest.
+    pub fn merge(&mut self, args: BookArgs) -> CliResult<&mut Self> {
+        macro_rules! merge {
+            (vec, $($field:ident),+) => {
+                $(
+                    if !args.$field.is_empty() {
+                        self.$field = args.$field;
+                    }
+                )+
+            };
+            ($($field:ident),+) => {
+                $(
+                    if let Some(value) = args.$field {
+                        self.$field = Some(value.into());
+                    }
+                )+
+            };
+        }
+
+        merge!(
+            title,
+            cover,
+            cover_layout,
+            cover_colors,
+            cover_format,
+            cover_max_size,
+            cover_quality,
+            output,
+            theme,
+            green_color,
+            spoiler_color,
+            orange_color,
+            caret_color,
+            spoiler_mode,
+            chapter_title_regex,
+            input_format,
+            op_match,
+            stdin_title,
+            sort,
+            description,
+            publisher,
+            language,
+            date,
+            rights,
+            source,
+            identifier,
+            series,
+            series_index,
+            metadata
+        );
+        merge!(
+            vec,
+            authors,
+            subjects,
+            author_file_as,
+            editors,
+            translators,
+            illustrators,
+            spoiler_syntaxes,
+            posts
+        );
+
+        if !args.stylesheets.is_empty() {
+            self.stylesheets = args.stylesheets.into_iter().map(PathBuf::from).collect();
+        }
+        self.css_vars.extend(args.css_vars);
+
+        self.no_generated_cover |= args.no_generated_cover;
+        self.normalize_cover |= args.normalize_cover;
+        self.chapter_title_first_line |= args.chapter_title_first_line;
+        self.no_post_headers |= args.no_post_headers;
+        self.reproducible |= args.reproducible;
+
+        for (number, title) in args.chapter_titles {
+            match self.chapters.get_mut(number - 1) {
+                Some(chapter) => chapter.title = Some(title),
+                None => {
+                    return Err(CliError::from(format!(
+                        "can't set title of chapter {}, the book has {} chapter{}",
+                        number,
+                        self.chapters.len(),
+                        if self.chapters.len() == 1 { "" } else { "s" }
+                    )))
+                }
+            };
+        }
+
+        Ok(self)
+    }
+
+    /// Fills the metadata that isn't set with the one of the manifest given as `metadata`, if
+    /// any.
+    pub fn load_metadata(&mut self) -> CliResult<&mut Self> {
+        let path = match &self.metadata {
+            Some(path) => path.clone(),
+            None => return Ok(self),
+        };
+
+        info!("Reading metadata from {:?}", path.display());
+        let sidecar = Manifest::from_file(&path)?;
+
+        macro_rules! fill {
+            (vec, $($field:ident),+) => {
+                $(
+                    if self.$field.is_empty() {
+                        self.$field = sidecar.$field;
+                    }
+                )+
+            };
+            ($($field:ident),+) => {
+                $(
+                    if self.$field.is_none() {
+                        self.$field = sidecar.$field;
+                    }
+                )+
+            };
+        }
+
+        fill!(
+            title,
+            description,
+            publisher,
+            language,
+            date,
+            rights,
+            source,
+            identifier,
+            series,
+            series_index
+        );
+        fill!(
+            vec,
+            authors,
+            subjects,
+            author_file_as,
+            editors,
+            translators,
+            illustrators
+        );
+
+        Ok(self)
+    }
+
+    /// Replaces chapters given as directories or glob patterns with one chapter for each file
+    /// they expand to, sorted in the given order.
+    ///
+    /// The title of such a chapter is only kept if it expands to a single file.
+    pub fn expand(&mut self, order: SortOrder) -> CliResult<&mut Self> {
+        let mut chapters = Vec::with_capacity(self.chapters.len());
+        for chapter in self.chapters.drain(..) {
+            let files = inputs::expand(&chapter.file, order)?;
+            if files.len() > 1 && chapter.title.is_some() {
+                warn!(
+                    "Ignoring title of {:?}, it expands to {} files",
+                    chapter.file.display(),
+                    files.len()
+                );
+            }
+
+            let title = chapter.title.filter(|_| files.len() == 1);
+            chapters.extend(files.into_iter().map(|file| ManifestChapter {
+                file,
+                title: title.clone(),
+            }));
+        }
+
+        self.chapters = chapters;
+        Ok(self)
+    }
+
+    /// Appends chapters from their files, which are titled after their file stem.
+    pub fn files<I, S>(&mut self, files: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<PathBuf>,
+    {
+        self.chapters
+            .extend(files.into_iter().map(|file| ManifestChapter {
+                file: file.into(),
+                title: None,
+            }));
+        self
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn toml_and_json_manifests() {
+        let toml: Manifest = toml::from_str(
+            r##"
+            title = "Paste"
+            author = "Author"
+            tags = ["SFW"]
+            green-color = "#0f0"
+            spoiler-syntaxes = ["bbcode", "discord"]
+            spoiler-mode = "footnote"
+            chapters = ["001.txt", { file = "002.txt", title = "Part 2" }]
+            "##,
+        )
+        .unwrap();
+        let json: Manifest = serde_json::from_str(
+            r##"{
+                "title": "Paste",
+                "authors": ["Author"],
+                "subjects": ["SFW"],
+                "green-color": "#0f0",
+                "spoiler-syntaxes": ["bbcode", "discord"],
+                "spoiler-mode": "footnote",
+                "chapters": ["001.txt", {"file": "002.txt", "title": "Part 2"}]
+            }"##,
+        )
+        .unwrap();
+
+        for manifest in [toml, json] {
+            assert_eq!(manifest.title.as_deref(), Some("Paste"));
+            assert_eq!(manifest.authors, ["Author"]);
+            assert_eq!(manifest.subjects, ["SFW"]);
+            assert_eq!(manifest.green_color.as_deref(), Some("#0f0"));
+            assert!(matches!(
+                manifest.spoiler_syntaxes.as_slice(),
+                [Spoiler::Bbcode, Spoiler::Discord]
+            ));
+            assert!(matches!(
+                manifest.spoiler_mode,
+                Some(SpoilerRendering::Footnote)
+            ));
+            assert_eq!(
+                manifest.chapters,
+                [
+                    ManifestChapter {
+                        file: "001.txt".into(),
+                        title: None
+                    },
+                    ManifestChapter {
+                        file: "002.txt".into(),
+                        title: Some("Part 2".into())
+                    }
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn manifest_to_toml() {
+        let manifest = Manifest {
+            title: Some("Paste".into()),
+            authors: vec!["Author".into()],
+            spoiler_syntaxes: vec![Spoiler::Discord],
+            spoiler_mode: Some(SpoilerRendering::Footnote),
+            cover_max_size: Some((800, 1200)),
+            chapters: vec![
+                ManifestChapter {
+                    file: "001.txt".into(),
+                    title: None,
+                },
+                ManifestChapter {
+                    file: "002.txt".into(),
+                    title: Some("Part 2".into()),
+                },
+            ],
+            ..Manifest::default()
+        };
+
+        let toml = manifest.to_toml().unwrap();
+        assert!(!toml.contains("output"));
+        assert!(toml.contains(r#"cover-max-size = "800x1200""#));
+
+        let read: Manifest = toml::from_str(&toml).unwrap();
+        assert_eq!(read.title, manifest.title);
+        assert_eq!(read.authors, manifest.authors);
+        assert!(matches!(
+            read.spoiler_syntaxes.as_slice(),
+            [Spoiler::Discord]
+        ));
+        assert!(matches!(
+            read.spoiler_mode,
+            Some(SpoilerRendering::Footnote)
+        ));
+        assert_eq!(read.cover_max_size, manifest.cover_max_size);
+        assert_eq!(read.chapters, manifest.chapters);
+    }
+
+    #[test]
+    fn invalid_manifests() {
+        assert!(toml::from_str::<Manifest>(r##"green-color = "green""##).is_err());
+        assert!(toml::from_str::<Manifest>(r##"spoiler-mode = "hidden""##).is_err());
+        assert!(toml::from_str::<Manifest>(r##"unknown = 1"##).is_err());
+        assert!(toml::from_str::<Manifest>(r##"cover-max-size = "800""##).is_err());
+        assert!(toml::from_str::<Manifest>(r##"cover-quality = 0"##).is_err());
+        assert!(toml::from_str::<Manifest>(r##"css-vars = { "a b" = "1" }"##).is_err());
+
+        let mut manifest = Manifest::default();
+        assert!(manifest
+            .merge(BookArgs {
+                chapter_titles: vec![(1, "Part 1".into())],
+                ..BookArgs::default()
+            })
+            .is_err());
+    }
+
+    #[test]
+    fn relative_paths() {
+        let mut manifest: Manifest = toml::from_str(
+            r##"
+            output = "paste.epub"
+            stylesheets = ["style.css"]
+            chapters = ["001.txt", "-"]
+            "##,
+        )
+        .unwrap();
+        manifest.relative_to(Path::new("story"));
+
+        assert_eq!(manifest.output, Some(PathBuf::from("story/paste.epub")));
+        assert_eq!(manifest.stylesheets, [PathBuf::from("story/style.css")]);
+        assert_eq!(
+            manifest
+                .chapters
+                .iter()
+                .map(|chapter| chapter.file.as_path())
+                .collect::<Vec<&Path>>(),
+            [Path::new("story/001.txt"), Path::new("-")]
+        );
+    }
+
+    #[test]
+    fn flags_override_manifest() {
+        let mut manifest: Manifest = toml::from_str(
+            r##"
+            title = "Paste"
+            authors = ["Author"]
+            output = "paste.epub"
+            css-vars = { --post-spacing = "2em", text-color = "#333" }
+            "##,
+        )
+        .unwrap();
+        manifest
+            .files(["001.txt"])
+            .merge(BookArgs {
+                title: Some("Other".into()),
+                chapter_titles: vec![(1, "Part 1".into())],
+                css_vars: vec![("text-color".into(), "#000".into())],
+                ..BookArgs::default()
+            })
+            .unwrap();
+
+        assert_eq!(manifest.title.as_deref(), Some("Other"));
+        assert_eq!(manifest.chapters[0].title.as_deref(), Some("Part 1"));
+        assert_eq!(manifest.authors, ["Author"]);
+        assert_eq!(manifest.output, Some(PathBuf::from("paste.epub")));
+        assert_eq!(
+            manifest
+                .css_vars
+                .into_iter()
+                .collect::<Vec<(String, String)>>(),
+            [
+                ("post-spacing".into(), "2em".into()),
+                ("text-color".into(), "#000".into())
+            ]
+        );
+    }
+}
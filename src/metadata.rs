@@ -0,0 +1,353 @@
+//! Metadata of a book, written as Dublin Core elements in its package document.
+
+use html_escape::{encode_double_quoted_attribute, encode_text};
+
+use crate::errors::{CliError, CliResult};
+
+/// Language of books that don't set one.
+pub const DEFAULT_LANGUAGE: &str = "en";
+
+/// Languages written from right to left, when their tag doesn't give a script.
+const RTL_LANGUAGES: [&str; 18] = [
+    "ar", "arc", "ckb", "dv", "fa", "glk", "he", "iw", "ji", "khw", "mzn", "pnb", "prs", "ps",
+    "sd", "syr", "ug", "ur",
+];
+/// Scripts written from right to left.
+const RTL_SCRIPTS: [&str; 9] = [
+    "adlm", "arab", "hebr", "mand", "nkoo", "rohg", "samr", "syrc", "thaa",
+];
+
+/// Tells if a language tag is well formed as described in BCP 47, which doesn't check its
+/// subtags are registered.
+fn is_well_formed_language(tag: &str) -> bool {
+    let is_alpha = |subtag: &str| subtag.bytes().all(|byte| byte.is_ascii_alphabetic());
+    let is_digit = |subtag: &str| subtag.bytes().all(|byte| byte.is_ascii_digit());
+    let is_alphanum = |subtag: &str| subtag.bytes().all(|byte| byte.is_ascii_alphanumeric());
+
+    let subtags: Vec<&str> = tag.split('-').collect();
+    if subtags
+        .iter()
+        .any(|subtag| subtag.is_empty() || subtag.len() > 8 || !is_alphanum(subtag))
+    {
+        return false;
+    }
+
+    let mut subtags = subtags.into_iter().peekable();
+    let mut next_if =
+        |predicate: &dyn Fn(&str) -> bool| subtags.next_if(|subtag| predicate(subtag));
+
+    // A tag that is only for private use.
+    if next_if(&|subtag| subtag.eq_ignore_ascii_case("x")).is_some() {
+        return next_if(&|_| true).is_some();
+    }
+
+    // Language, extended languages, script, region and variants, followed by extensions and
+    // private use subtags that each start with a single character.
+    let language = match next_if(&|subtag| is_alpha(subtag) && subtag.len() != 1) {
+        Some(language) => language,
+        None => return false,
+    };
+    if (2..=3).contains(&language.len()) {
+        for _ in 0..3 {
+            if next_if(&|subtag| subtag.len() == 3 && is_alpha(subtag)).is_none() {
+                break;
+            }
+        }
+    }
+    next_if(&|subtag| subtag.len() == 4 && is_alpha(subtag));
+    next_if(&|subtag| {
+        (subtag.len() == 2 && is_alpha(subtag)) || (subtag.len() == 3 && is_digit(subtag))
+    });
+    while next_if(&|subtag| {
+        (5..=8).contains(&subtag.len())
+            || (subtag.len() == 4 && subtag.as_bytes()[0].is_ascii_digit())
+    })
+    .is_some()
+    {}
+
+    while let Some(singleton) = next_if(&|subtag| subtag.len() == 1) {
+        if singleton.eq_ignore_ascii_case("x") {
+            if next_if(&|_| true).is_none() {
+                return false;
+            }
+            while next_if(&|_| true).is_some() {}
+            break;
+        }
+        if next_if(&|subtag| subtag.len() >= 2).is_none() {
+            return false;
+        }
+        while next_if(&|subtag| subtag.len() >= 2).is_some() {}
+    }
+
+    next_if(&|_| true).is_none()
+}
+
+/// Checks a language tag is well formed as described in BCP 47, like `en`, `es-419` or
+/// `zh-Hant-TW`.
+pub fn validate_language(tag: &str) -> CliResult<()> {
+    if is_well_formed_language(tag) {
+        Ok(())
+    } else {
+        Err(CliError::from(format!(
+            "invalid language tag {:?}, it has to be a BCP 47 tag like `en`, `es-419` or `ja`",
+            tag
+        )))
+    }
+}
+
+/// Tells if a language is written from right to left, from the script given in its tag or
+/// else from the language itself.
+pub fn is_rtl_language(tag: &str) -> bool {
+    let mut subtags = tag.split('-');
+    let language = subtags.next().unwrap_or_default().to_ascii_lowercase();
+    let script = subtags
+        .take_while(|subtag| subtag.len() != 1)
+        .find(|subtag| subtag.len() == 4 && subtag.bytes().all(|byte| byte.is_ascii_alphabetic()));
+
+    match script {
+        Some(script) => RTL_SCRIPTS.contains(&script.to_ascii_lowercase().as_str()),
+        None => RTL_LANGUAGES.contains(&language.as_str()),
+    }
+}
+
+/// What a person did for the book, written as a MARC relator code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    Author,
+    Editor,
+    Translator,
+    Illustrator,
+}
+
+impl Role {
+    pub fn marc_code(self) -> &'static str {
+        match self {
+            Self::Author => "aut",
+            Self::Editor => "edt",
+            Self::Translator => "trl",
+            Self::Illustrator => "ill",
+        }
+    }
+
+    pub fn from_marc_code(code: &str) -> Option<Self> {
+        [
+            Self::Author,
+            Self::Editor,
+            Self::Translator,
+            Self::Illustrator,
+        ]
+        .into_iter()
+        .find(|role| role.marc_code() == code)
+    }
+}
+
+/// Person who took part in making the book.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Contributor {
+    pub name: String,
+    pub role: Role,
+    /// Name the person is sorted by, like `Doe, John`.
+    pub file_as: Option<String>,
+}
+
+impl Contributor {
+    pub fn new<S>(name: S, role: Role) -> Self
+    where
+        S: ToString,
+    {
+        Self {
+            name: name.to_string(),
+            role,
+            file_as: None,
+        }
+    }
+
+    pub fn with_file_as<S>(mut self, file_as: S) -> Self
+    where
+        S: ToString,
+    {
+        self.file_as = Some(file_as.to_string());
+        self
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Metadata {
+    pub title: String,
+    /// Authors are written as creators and everyone else as contributors.
+    pub contributors: Vec<Contributor>,
+    pub subjects: Vec<String>,
+    pub description: Option<String>,
+    pub publisher: Option<String>,
+    /// Language of the text as a BCP 47 tag [default: en].
+    pub language: Option<String>,
+    /// Date of publication in W3CDTF format, like `2021`, `2021-03` or `2021-03-04`, the
+    /// modification date is used if it isn't set.
+    pub date: Option<String>,
+    pub rights: Option<String>,
+    /// Where the book comes from, like the URL of the thread.
+    pub source: Option<String>,
+    /// Unique identifier of the book, a UUID is made up if it isn't set.
+    pub identifier: Option<String>,
+    /// Series the book belongs to.
+    pub series: Option<String>,
+    /// Position of the book in its series.
+    pub series_index: Option<f64>,
+}
+
+impl Metadata {
+    pub fn language(&self) -> &str {
+        self.language.as_deref().unwrap_or(DEFAULT_LANGUAGE)
+    }
+
+    pub fn authors(&self) -> impl Iterator<Item = &Contributor> {
+        self.contributors
+            .iter()
+            .filter(|contributor| contributor.role == Role::Author)
+    }
+
+    /// `metadata` element of the package document, `modified` being a date and time in
+    /// `YYYY-MM-DDThh:mm:ssZ` format.
+    pub(crate) fn to_opf(&self, identifier: &str, modified: &str, cover: bool) -> String {
+        let mut lines = vec![
+            format!(
+                r#"<dc:identifier id="epub-id-1">{}</dc:identifier>"#,
+                encode_text(identifier)
+            ),
+            format!("<dc:title>{}</dc:title>", encode_text(&self.title)),
+            format!(
+                "<dc:language>{}</dc:language>",
+                encode_text(self.language())
+            ),
+            format!(
+                "<dc:date>{}</dc:date>",
+                encode_text(self.date.as_deref().unwrap_or(modified))
+            ),
+        ];
+
+        let (mut creators, mut contributors) = (0, 0);
+        for contributor in &self.contributors {
+            let (element, id) = if contributor.role == Role::Author {
+                creators += 1;
+                ("dc:creator", format!("creator-{}", creators))
+            } else {
+                contributors += 1;
+                ("dc:contributor", format!("contributor-{}", contributors))
+            };
+
+            lines.push(format!(
+                r#"<{element} id="{id}">{}</{element}>"#,
+                encode_text(&contributor.name)
+            ));
+            lines.push(format!(
+                r##"<meta refines="#{id}" property="role" scheme="marc:relators">{}</meta>"##,
+                contributor.role.marc_code()
+            ));
+            if let Some(file_as) = &contributor.file_as {
+                lines.push(format!(
+                    r##"<meta refines="#{id}" property="file-as">{}</meta>"##,
+                    encode_text(file_as)
+                ));
+            }
+        }
+
+        for (element, value) in [
+            ("dc:publisher", &self.publisher),
+            ("dc:description", &self.description),
+            ("dc:rights", &self.rights),
+            ("dc:source", &self.source),
+        ] {
+            if let Some(value) = value {
+                lines.push(format!("<{element}>{}</{element}>", encode_text(value)));
+            }
+        }
+
+        for subject in &self.subjects {
+            lines.push(format!("<dc:subject>{}</dc:subject>", encode_text(subject)));
+        }
+
+        // Written both as an EPUB 3 collection and as the meta tags calibre uses.
+        if let Some(series) = &self.series {
+            lines.push(format!(
+                r#"<meta property="belongs-to-collection" id="collection-1">{}</meta>"#,
+                encode_text(series)
+            ));
+            lines.push(String::from(
+                r##"<meta refines="#collection-1" property="collection-type">series</meta>"##,
+            ));
+            if let Some(index) = self.series_index {
+                lines.push(format!(
+                    r##"<meta refines="#collection-1" property="group-position">{}</meta>"##,
+                    index
+                ));
+            }
+
+            lines.push(format!(
+                r#"<meta name="calibre:series" content="{}"/>"#,
+                encode_double_quoted_attribute(series)
+            ));
+            if let Some(index) = self.series_index {
+                lines.push(format!(
+                    r#"<meta name="calibre:series_index" content="{}"/>"#,
+                    index
+                ));
+            }
+        }
+
+        lines.push(format!(
+            r#"<meta property="dcterms:modified">{}</meta>"#,
+            modified
+        ));
+        if cover {
+            lines.push(String::from(
+                r#"<meta name="cover" content="cover-image"/>"#,
+            ));
+        }
+
+        format!(
+            "<metadata xmlns:dc=\"http://purl.org/dc/elements/1.1/\"\n            \
+             xmlns:opf=\"http://www.idpf.org/2007/opf\">\n{}\n  </metadata>",
+            lines
+                .iter()
+                .map(|line| format!("    {}", line))
+                .collect::<Vec<String>>()
+                .join("\n")
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn language_tags() {
+        for tag in [
+            "en",
+            "es-419",
+            "zh-Hant-TW",
+            "de-DE-1996",
+            "sr-Latn-RS-u-nu-latn",
+            "x-kl",
+        ] {
+            assert!(validate_language(tag).is_ok(), "{} is valid", tag);
+        }
+        for tag in [
+            "",
+            "en_US",
+            "en-",
+            "a",
+            "en-x",
+            "español",
+            "toolonglanguage",
+        ] {
+            assert!(validate_language(tag).is_err(), "{} is invalid", tag);
+        }
+
+        assert!(is_rtl_language("ar"));
+        assert!(is_rtl_language("he-IL"));
+        assert!(is_rtl_language("az-Arab"));
+        assert!(!is_rtl_language("ja"));
+        assert!(!is_rtl_language("ku-Latn"));
+    }
+}
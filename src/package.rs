@@ -0,0 +1,284 @@
+//! Package document of generated EPUBs, rewritten with the full metadata of the book.
+
+use std::io::{Cursor, Read, Write};
+use std::time::SystemTime;
+
+use regex::Regex;
+use sha2::{Digest, Sha256};
+use zip::{write::FileOptions, DateTime, ZipArchive, ZipWriter};
+
+use crate::content::with_language;
+use crate::errors::{CliError, CliResult, ResultExt};
+use crate::metadata::{is_rtl_language, Metadata};
+
+const PACKAGE_FILE: &str = "OEBPS/content.opf";
+/// Documents generated by `epub-builder`, which don't set their language.
+const NAVIGATION_FILES: [&str; 2] = ["OEBPS/nav.xhtml", "OEBPS/toc.xhtml"];
+const SPINE_START: &str = r#"<spine toc="ncx">"#;
+const METADATA_PATTERN: &str = r"(?s)<metadata\b.*?</metadata>";
+const UUID_PATTERN: &str = r"urn:uuid:[0-9a-fA-F-]{36}";
+/// Stands in for the identifier while the content is hashed.
+const NIL_UUID: &str = "urn:uuid:00000000-0000-0000-0000-000000000000";
+
+/// Time of the files in the archive, zip can't store times before 1980 so those are clamped.
+fn zip_time(date: &str) -> DateTime {
+    let part = |range: std::ops::Range<usize>| date[range].parse().unwrap_or_default();
+    DateTime::from_date_and_time(
+        part(0..4),
+        part(5..7) as u8,
+        part(8..10) as u8,
+        part(11..13) as u8,
+        part(14..16) as u8,
+        part(17..19) as u8,
+    )
+    .unwrap_or_default()
+}
+
+/// UUID URN made from a hash, with the version and variant bits of a custom (version 8) UUID.
+fn uuid_from_hash(hash: &[u8]) -> String {
+    let mut bytes = [0; 16];
+    bytes.copy_from_slice(&hash[..16]);
+    bytes[6] = (bytes[6] & 0x0f) | 0x80;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+
+    let hex: String = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+    format!(
+        "urn:uuid:{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
+
+/// Rewrites an EPUB generated by `epub-builder` with the metadata of the book, keeping the
+/// order of its files.
+///
+/// A reproducible book is given the modification date passed as `reproducible`, an identifier
+/// derived from its content unless it has one, and the same time for all of its files.
+pub(crate) fn repack(
+    epub: &[u8],
+    metadata: &Metadata,
+    cover: bool,
+    reproducible: Option<SystemTime>,
+) -> CliResult<Vec<u8>> {
+    let modified =
+        humantime::format_rfc3339_seconds(reproducible.unwrap_or_else(SystemTime::now)).to_string();
+    let metadata_regex = Regex::new(METADATA_PATTERN).unwrap();
+    let uuid_regex = Regex::new(UUID_PATTERN).unwrap();
+
+    let mut archive = ZipArchive::new(Cursor::new(epub))?;
+    let mut files = Vec::with_capacity(archive.len());
+    let mut generated_uuid = None;
+    for idx in 0..archive.len() {
+        let mut file = archive.by_index(idx)?;
+        let mut content = Vec::new();
+        file.read_to_end(&mut content)
+            .context(format!("failed to read {:?} from EPUB", file.name()))?;
+
+        // The identifier is filled in once the content is hashed.
+        if file.name() == PACKAGE_FILE {
+            let package = String::from_utf8_lossy(&content);
+            generated_uuid = uuid_regex
+                .find(&package)
+                .map(|uuid| uuid.as_str().to_string());
+            if !metadata_regex.is_match(&package) {
+                return Err(CliError::from(String::from(
+                    "generated package document has no metadata",
+                )));
+            }
+
+            let rendered = metadata.to_opf(NIL_UUID, &modified, cover);
+            let mut package = metadata_regex
+                .replace(&package, regex::NoExpand(&rendered))
+                .into_owned();
+            if is_rtl_language(metadata.language()) {
+                package = package.replacen(
+                    SPINE_START,
+                    r#"<spine toc="ncx" page-progression-direction="rtl">"#,
+                    1,
+                );
+            }
+            content = package.into_bytes();
+        } else if NAVIGATION_FILES.contains(&file.name()) {
+            content =
+                with_language(&String::from_utf8_lossy(&content), metadata.language()).into_bytes();
+        }
+
+        files.push((file.name().to_string(), file.compression(), content));
+    }
+
+    let identifier = match (&metadata.identifier, generated_uuid) {
+        (Some(identifier), _) => identifier.clone(),
+        (None, Some(uuid)) if reproducible.is_none() => uuid,
+        _ => {
+            let mut hasher = Sha256::new();
+            for (name, _, content) in &files {
+                hasher.update((name.len() as u64).to_be_bytes());
+                hasher.update(name.as_bytes());
+                hasher.update((content.len() as u64).to_be_bytes());
+                hasher.update(content);
+            }
+            uuid_from_hash(&hasher.finalize())
+        }
+    };
+    debug!("Using identifier {} and date {}", identifier, modified);
+
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+    writer.set_comment("");
+    for (name, compression, content) in files {
+        let content = if name == PACKAGE_FILE {
+            let placeholder = format!(">{}<", NIL_UUID);
+            let identifier = format!(">{}<", html_escape::encode_text(&identifier));
+            String::from_utf8_lossy(&content)
+                .replacen(&placeholder, &identifier, 1)
+                .into_bytes()
+        } else {
+            content
+        };
+
+        let mut options = FileOptions::default().compression_method(compression);
+        if reproducible.is_some() {
+            options = options
+                .last_modified_time(zip_time(&modified))
+                .unix_permissions(0o644);
+        }
+        writer.start_file(name.as_str(), options)?;
+        writer
+            .write_all(&content)
+            .context(format!("failed to write {:?} into EPUB", name))?;
+    }
+
+    Ok(writer.finish()?.into_inner())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use crate::book::{BookBuilder, Chapter};
+    use crate::metadata::{Contributor, Role};
+
+    fn generate(title: &str) -> Vec<u8> {
+        let mut book = BookBuilder::new(title)
+            .author("Author")
+            .reproducible(UNIX_EPOCH + Duration::from_secs(1_700_000_000))
+            .chapter(Chapter::new("001", ">be me"))
+            .build()
+            .unwrap();
+
+        let mut bytes: Vec<u8> = Vec::new();
+        book.generate(&mut bytes).unwrap();
+        bytes
+    }
+
+    fn package(epub: &[u8]) -> String {
+        let mut archive = ZipArchive::new(Cursor::new(epub)).unwrap();
+        let mut package = String::new();
+        archive
+            .by_name(PACKAGE_FILE)
+            .unwrap()
+            .read_to_string(&mut package)
+            .unwrap();
+        package
+    }
+
+    #[test]
+    fn same_inputs_same_bytes() {
+        let epub = generate("Paste");
+        assert_eq!(epub, generate("Paste"));
+
+        let package = package(&epub);
+        assert!(package.contains("<dc:date>2023-11-14T22:13:20Z</dc:date>"));
+        assert!(
+            package.contains(r#"<meta property="dcterms:modified">2023-11-14T22:13:20Z</meta>"#)
+        );
+        assert!(!package.contains(NIL_UUID));
+
+        let uuid = Regex::new(UUID_PATTERN).unwrap();
+        let find_uuid = |package: &str| uuid.find(package).unwrap().as_str().to_string();
+        assert_ne!(
+            find_uuid(&package),
+            find_uuid(&self::package(&generate("Other paste")))
+        );
+    }
+
+    #[test]
+    fn full_metadata() {
+        let mut book = BookBuilder::new("Paste & more")
+            .author("Author")
+            .contributor(Contributor::new("Editor", Role::Editor).with_file_as("Editor, The"))
+            .subject("SFW")
+            .publisher("Anon <Press>")
+            .identifier("urn:isbn:9780000000000")
+            .series("Saga & more")
+            .series_index(2.5)
+            .chapter(Chapter::new("001", ">be me"))
+            .build()
+            .unwrap();
+
+        let mut bytes: Vec<u8> = Vec::new();
+        book.generate(&mut bytes).unwrap();
+
+        let package = package(&bytes);
+        for element in [
+            r#"<dc:identifier id="epub-id-1">urn:isbn:9780000000000</dc:identifier>"#,
+            "<dc:title>Paste &amp; more</dc:title>",
+            "<dc:language>en</dc:language>",
+            r#"<dc:creator id="creator-1">Author</dc:creator>"#,
+            r##"<meta refines="#creator-1" property="role" scheme="marc:relators">aut</meta>"##,
+            r#"<dc:contributor id="contributor-1">Editor</dc:contributor>"#,
+            r##"<meta refines="#contributor-1" property="role" scheme="marc:relators">edt</meta>"##,
+            r##"<meta refines="#contributor-1" property="file-as">Editor, The</meta>"##,
+            "<dc:publisher>Anon &lt;Press&gt;</dc:publisher>",
+            "<dc:subject>SFW</dc:subject>",
+            r#"<meta property="belongs-to-collection" id="collection-1">Saga &amp; more</meta>"#,
+            r##"<meta refines="#collection-1" property="collection-type">series</meta>"##,
+            r##"<meta refines="#collection-1" property="group-position">2.5</meta>"##,
+            r#"<meta name="calibre:series" content="Saga &amp; more"/>"#,
+            r#"<meta name="calibre:series_index" content="2.5"/>"#,
+        ] {
+            assert!(package.contains(element), "missing {}", element);
+        }
+        assert!(!Regex::new(UUID_PATTERN).unwrap().is_match(&package));
+    }
+
+    #[test]
+    fn right_to_left_language() {
+        let mut book = BookBuilder::new("Paste")
+            .author("Author")
+            .language("he")
+            .chapter(Chapter::new("001", ">be me"))
+            .build()
+            .unwrap();
+
+        let mut bytes: Vec<u8> = Vec::new();
+        book.generate(&mut bytes).unwrap();
+
+        assert!(package(&bytes).contains(r#"<spine toc="ncx" page-progression-direction="rtl">"#));
+
+        let mut archive = ZipArchive::new(Cursor::new(&bytes)).unwrap();
+        for file in ["OEBPS/nav.xhtml", "OEBPS/content/paste-001.xhtml"] {
+            let mut xhtml = String::new();
+            archive
+                .by_name(file)
+                .unwrap()
+                .read_to_string(&mut xhtml)
+                .unwrap();
+            assert!(
+                xhtml.contains(r#" lang="he" xml:lang="he" dir="rtl""#),
+                "{} is in Hebrew",
+                file
+            );
+        }
+
+        assert!(BookBuilder::new("Paste")
+            .language("en_US")
+            .chapter(Chapter::new("001", ">be me"))
+            .build()
+            .is_err());
+    }
+}
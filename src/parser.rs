@@ -1,162 +1,892 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::check::{Diagnostic, Position, Problem};
 use crate::tag::{Child, Tag};
 
 pub const RESET_FOREGROUND_CLASS: &str = "icolor";
-const SPOILER_OPEN_TAG: &str = "[spoiler]";
-const SPOILER_CLOSE_TAG: &str = "[/spoiler]";
+pub const QUOTELINK_CLASS: &str = "quotelink";
+const GREENTEXT_PREFIX: char = '>';
+const POST_REF_PREFIX: &str = ">>";
+pub(crate) const POST_NUMBER_PREFIX: &str = "No.";
 
-#[derive(Debug)]
-enum Token {
-    SpoilerOpen,
-    SpoilerClose,
-    Text(String),
+/// Pair of delimiters that enclose a spoiler.
+///
+/// Delimiters can be symmetric, like Discord's `||`, in which case the same delimiter both opens
+/// and closes the spoiler.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpoilerSyntax {
+    pub open: String,
+    pub close: String,
+}
+
+impl SpoilerSyntax {
+    pub fn new<O, C>(open: O, close: C) -> Self
+    where
+        O: ToString,
+        C: ToString,
+    {
+        Self {
+            open: open.to_string(),
+            close: close.to_string(),
+        }
+    }
+
+    pub fn symmetric<D>(delimiter: D) -> Self
+    where
+        D: ToString,
+    {
+        let delimiter = delimiter.to_string();
+        Self::new(delimiter.clone(), delimiter)
+    }
+
+    /// `[spoiler]text[/spoiler]`
+    pub fn bbcode() -> Self {
+        Self::new("[spoiler]", "[/spoiler]")
+    }
+
+    /// `[s]text[/s]`, which takes precedence over the BBCode tag for strikethrough.
+    pub fn bbcode_short() -> Self {
+        Self::new("[s]", "[/s]")
+    }
+
+    /// `||text||`
+    pub fn discord() -> Self {
+        Self::symmetric("||")
+    }
+
+    /// `>!text!<`
+    pub fn reddit() -> Self {
+        Self::new(">!", "!<")
+    }
+
+    pub fn is_symmetric(&self) -> bool {
+        self.open == self.close
+    }
+}
+
+impl Default for SpoilerSyntax {
+    fn default() -> Self {
+        Self::bbcode()
+    }
 }
 
-macro_rules! tokenize_spoiler {
-    ($ref:expr, $idx:expr, $tokens:expr, $line:ident, $token_kind:ident) => {
-        // Push the text before the tag if any.
-        if $idx != $ref {
-            $tokens.push(Token::Text($line[$ref..$idx].into()));
+/// Formatting that is enclosed between an opening and a closing delimiter.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Format {
+    /// Spoiler of the syntax with the given index.
+    Spoiler(usize),
+    Bold,
+    Italic,
+    Underline,
+    Strikethrough,
+    Code,
+}
+
+impl Format {
+    pub(crate) fn tag_name(&self) -> &'static str {
+        match self {
+            Format::Spoiler(_) => "span",
+            Format::Bold => "strong",
+            Format::Italic => "em",
+            Format::Underline => "u",
+            Format::Strikethrough => "s",
+            Format::Code => "code",
         }
-        $tokens.push(Token::$token_kind);
-    };
-    ($idx:expr, $tokens:expr, $line:ident, $token_kind:ident) => {
-        tokenize_spoiler!(0, $idx, $tokens, $line, $token_kind);
-    };
+    }
 }
 
-macro_rules! push_line_buffer {
-    ($line:ident, $idx:expr, $tag:expr) => {
-        $line = &$line[$idx + $tag.len()..];
-    };
+/// BBCode tags for formatting other than spoilers.
+pub(crate) const BBCODE_TAGS: [(Format, &str, &str); 5] = [
+    (Format::Bold, "[b]", "[/b]"),
+    (Format::Italic, "[i]", "[/i]"),
+    (Format::Underline, "[u]", "[/u]"),
+    (Format::Strikethrough, "[s]", "[/s]"),
+    (Format::Code, "[code]", "[/code]"),
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Token {
+    /// Opening delimiter of a format, symmetric delimiters are always tokenized as opening ones.
+    Open(Format),
+    Close(Format),
+    /// Reference to another post, as in `>>123456`.
+    PostRef(u64),
+    /// Number of the post the line belongs to, as in `No.123456` at the end of its header.
+    PostNumber(u64),
+    Text(String),
+}
+
+/// Parses the post number that follows `prefix` at the start of `text`, returning it along
+/// with the byte length of the whole match.
+fn match_number(text: &str, prefix: &str) -> Option<(u64, usize)> {
+    let digits = text.strip_prefix(prefix)?;
+    let len = digits
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(digits.len());
+
+    digits[..len]
+        .parse()
+        .ok()
+        .map(|number| (number, prefix.len() + len))
+}
+
+/// Tells if the text after a post number is what can follow it in a post header, which is
+/// nothing but `▶` and references to the replies to the post.
+fn ends_post_header(text: &str) -> bool {
+    text.split_whitespace().all(|word| {
+        word == "▶"
+            || word.strip_prefix(POST_REF_PREFIX).is_some_and(|digits| {
+                !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit())
+            })
+    })
+}
+
+/// Matches a token at the start of `text`, returning it along with its byte length.
+///
+/// `at_word_start` tells if `text` isn't preceded by an alphanumeric character.
+fn match_token(
+    text: &str,
+    at_word_start: bool,
+    spoilers: &[SpoilerSyntax],
+) -> Option<(Token, usize)> {
+    // Spoilers go first so they take precedence over a BBCode tag with the same delimiters.
+    let spoilers = spoilers.iter().enumerate().map(|(idx, syntax)| {
+        (
+            Format::Spoiler(idx),
+            syntax.open.as_str(),
+            syntax.close.as_str(),
+        )
+    });
+
+    for (format, open, close) in spoilers.chain(BBCODE_TAGS) {
+        if text.starts_with(open) {
+            return Some((Token::Open(format), open.len()));
+        } else if text.starts_with(close) {
+            return Some((Token::Close(format), close.len()));
+        }
+    }
+
+    if let Some((number, len)) = match_number(text, POST_REF_PREFIX) {
+        Some((Token::PostRef(number), len))
+    } else if at_word_start {
+        match_number(text, POST_NUMBER_PREFIX)
+            .filter(|(_, len)| ends_post_header(&text[*len..]))
+            .map(|(number, len)| (Token::PostNumber(number), len))
+    } else {
+        None
+    }
 }
 
-fn tokenize<S: ?Sized>(line: &S) -> Vec<Token>
+/// Splits the line with the given number into tokens, each along with the position where it
+/// starts.
+fn tokenize<S>(number: usize, line: &S, spoilers: &[SpoilerSyntax]) -> Vec<(Position, Token)>
 where
-    S: AsRef<str>,
+    S: AsRef<str> + ?Sized,
 {
-    let mut line = line.as_ref();
-    let mut tokens: Vec<Token> = Vec::new();
-
-    loop {
-        match (line.find(SPOILER_OPEN_TAG), line.find(SPOILER_CLOSE_TAG)) {
-            (Some(start_idx), Some(end_idx)) => {
-                // While both tags are in the same line, they could be in any order.
-                if start_idx < end_idx {
-                    tokenize_spoiler!(start_idx, tokens, line, SpoilerOpen);
-                    tokenize_spoiler!(
-                        start_idx + SPOILER_OPEN_TAG.len(),
-                        end_idx,
-                        tokens,
-                        line,
-                        SpoilerClose
-                    );
-                    push_line_buffer!(line, end_idx, SPOILER_CLOSE_TAG);
-                } else {
-                    tokenize_spoiler!(end_idx, tokens, line, SpoilerClose);
-                    tokenize_spoiler!(
-                        end_idx + SPOILER_CLOSE_TAG.len(),
-                        start_idx,
-                        tokens,
-                        line,
-                        SpoilerOpen
-                    );
-                    push_line_buffer!(line, start_idx, SPOILER_OPEN_TAG);
+    let line = line.as_ref();
+    let mut tokens: Vec<(Position, Token)> = Vec::new();
+
+    let mut text_start = (0, Position::new(number, 1));
+    let mut idx = 0;
+    let mut position = Position::new(number, 1);
+    let mut at_word_start = true;
+
+    while let Some(c) = line[idx..].chars().next() {
+        match match_token(&line[idx..], at_word_start, spoilers) {
+            Some((token, len)) => {
+                // Push the text before the token if any.
+                if text_start.0 != idx {
+                    tokens.push((text_start.1, Token::Text(line[text_start.0..idx].into())));
                 }
+                tokens.push((position, token));
+
+                position.column += line[idx..idx + len].chars().count();
+                idx += len;
+                text_start = (idx, position);
+                at_word_start = true;
             }
-            (Some(idx), None) => {
-                tokenize_spoiler!(idx, tokens, line, SpoilerOpen);
-                push_line_buffer!(line, idx, SPOILER_OPEN_TAG);
-            }
-            (None, Some(idx)) => {
-                tokenize_spoiler!(idx, tokens, line, SpoilerClose);
-                push_line_buffer!(line, idx, SPOILER_CLOSE_TAG);
+            None => {
+                idx += c.len_utf8();
+                position.column += 1;
+                at_word_start = !c.is_alphanumeric();
             }
-            (None, None) => break,
-        };
+        }
     }
 
-    if !line.is_empty() {
-        tokens.push(Token::Text(line.into()))
+    if text_start.0 != line.len() {
+        tokens.push((text_start.1, Token::Text(line[text_start.0..].into())))
     }
 
     tokens
 }
 
-macro_rules! __push_span_tag {
-    ($children:ident, $text:expr) => {
-        let mut spoiler = Tag::new("span");
-        spoiler.child($text);
-        $children.push(spoiler.into());
-    };
+/// Line with its markup already known, for text that doesn't come in greentext format.
+///
+/// Its text is taken as is, without looking for spoilers, formatting or post references in it.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MarkedLine {
+    tokens: Vec<Token>,
 }
 
-macro_rules! push_span_tag {
-    ($children:ident, $text:expr) => {
-        match $children.last_mut() {
-            Some(last) => {
-                if let Child::Tag(tag) = last {
-                    tag.child($text);
-                } else {
-                    __push_span_tag!($children, $text);
-                }
+impl MarkedLine {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn text<S>(&mut self, text: S) -> &mut Self
+    where
+        S: ToString,
+    {
+        let text = text.to_string();
+        if !text.is_empty() {
+            self.tokens.push(Token::Text(text));
+        }
+        self
+    }
+
+    pub fn open_spoiler(&mut self) -> &mut Self {
+        self.tokens.push(Token::Open(Format::Spoiler(0)));
+        self
+    }
+
+    pub fn close_spoiler(&mut self) -> &mut Self {
+        self.tokens.push(Token::Close(Format::Spoiler(0)));
+        self
+    }
+
+    pub fn open_code(&mut self) -> &mut Self {
+        self.tokens.push(Token::Open(Format::Code));
+        self
+    }
+
+    pub fn close_code(&mut self) -> &mut Self {
+        self.tokens.push(Token::Close(Format::Code));
+        self
+    }
+
+    /// Reference to another post, as in `>>123456`.
+    pub fn post_ref(&mut self, number: u64) -> &mut Self {
+        self.tokens.push(Token::PostRef(number));
+        self
+    }
+
+    /// Number of the post the line belongs to, as in `No.123456`.
+    pub fn post_number(&mut self, number: u64) -> &mut Self {
+        self.tokens.push(Token::PostNumber(number));
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    pub(crate) fn tokens(&self) -> &[Token] {
+        &self.tokens
+    }
+
+    /// Tokens along with their position, as if the line was written in greentext format.
+    fn positioned_tokens(&self, number: usize) -> Vec<(Position, Token)> {
+        let mut position = Position::new(number, 1);
+
+        self.tokens
+            .iter()
+            .map(|token| {
+                let start = position;
+                position.column += match token {
+                    Token::Text(text) => text.chars().count(),
+                    Token::PostRef(number) => POST_REF_PREFIX.len() + number.to_string().len(),
+                    Token::PostNumber(number) => {
+                        POST_NUMBER_PREFIX.len() + number.to_string().len()
+                    }
+                    _ => 0,
+                };
+                (start, token.clone())
+            })
+            .collect()
+    }
+}
+
+/// Index of the posts referenced with `>>123456` in a set of chapters, pointing to the file of
+/// the chapter where each post is found.
+///
+/// A post is found where its number is written as `No.123456` at the end of a post header line,
+/// only its first appearance is taken into account.
+#[derive(Debug, Default, Clone)]
+pub struct PostIndex {
+    anchors: HashMap<u64, String>,
+}
+
+impl PostIndex {
+    /// Creates an index from pairs of chapter file and its text in greentext format.
+    pub fn new<'a, I, F>(chapters: I) -> Self
+    where
+        I: IntoIterator<Item = (F, &'a str)>,
+        F: ToString,
+    {
+        Self::from_tokens(
+            chapters
+                .into_iter()
+                .map(|(file, text)| (file, Self::tokenize_text(text))),
+        )
+    }
+
+    /// Tokens of a text in greentext format that matter to the index.
+    pub(crate) fn tokenize_text(text: &str) -> Vec<Token> {
+        text.lines()
+            .enumerate()
+            .flat_map(|(idx, line)| tokenize(idx + 1, line, &[]))
+            .map(|(_, token)| token)
+            .collect()
+    }
+
+    /// Creates an index from pairs of chapter file and the tokens of its text.
+    pub(crate) fn from_tokens<I, F, T>(chapters: I) -> Self
+    where
+        I: IntoIterator<Item = (F, T)>,
+        F: ToString,
+        T: IntoIterator<Item = Token>,
+    {
+        let mut found: HashMap<u64, String> = HashMap::new();
+        let mut referenced: HashSet<u64> = HashSet::new();
+
+        for (file, tokens) in chapters {
+            let file = file.to_string();
+
+            for token in tokens {
+                match token {
+                    Token::PostNumber(number) => {
+                        found.entry(number).or_insert_with(|| file.clone());
+                    }
+                    Token::PostRef(number) => {
+                        referenced.insert(number);
+                    }
+                    _ => {}
+                };
             }
-            None => {
-                __push_span_tag!($children, $text);
+        }
+
+        found.retain(|number, _| referenced.contains(number));
+
+        Self { anchors: found }
+    }
+
+    /// Adds a post that is found in the given chapter file, replacing any previous location.
+    pub fn insert<F>(&mut self, number: u64, file: F)
+    where
+        F: ToString,
+    {
+        self.anchors.insert(number, file.to_string());
+    }
+
+    pub fn file(&self, number: u64) -> Option<&str> {
+        self.anchors.get(&number).map(String::as_str)
+    }
+
+    /// Link to the given post, if it is found in any chapter.
+    pub fn href(&self, number: u64) -> Option<String> {
+        self.file(number)
+            .map(|file| format!("{}#{}", file, post_anchor(number)))
+    }
+}
+
+/// Highlight for lines that start with a given prefix, besides greentext.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Highlight {
+    pub prefix: String,
+    /// CSS class given to the highlighted lines.
+    pub class: String,
+    /// RGB color of the highlight in hexadecimal notation.
+    pub color: String,
+}
+
+impl Highlight {
+    pub fn new<P, C, S>(prefix: P, class: C, color: S) -> Self
+    where
+        P: ToString,
+        C: ToString,
+        S: ToString,
+    {
+        Self {
+            prefix: prefix.to_string(),
+            class: class.to_string(),
+            color: color.to_string(),
+        }
+    }
+
+    /// Highlight for lines starting with `<`.
+    pub fn orangetext<S>(color: S) -> Self
+    where
+        S: ToString,
+    {
+        Self::new('<', "orangetext", color)
+    }
+
+    /// Highlight for lines starting with `^`.
+    pub fn caret<S>(color: S) -> Self
+    where
+        S: ToString,
+    {
+        Self::new('^', "caret", color)
+    }
+}
+
+/// How spoilers are rendered.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum SpoilerMode {
+    /// Text is hidden with the same color as its background.
+    #[default]
+    Blackout,
+    /// Text is moved to EPUB3 popup footnotes, leaving a link to them in its place.
+    Footnote,
+    /// Text is moved to endnotes at the end of the chapter, leaving a link to them in its place.
+    Endnote,
+    /// Text is left as is, enclosed in brackets.
+    Inline,
+}
+
+impl SpoilerMode {
+    fn is_note(&self) -> bool {
+        matches!(self, SpoilerMode::Footnote | SpoilerMode::Endnote)
+    }
+}
+
+/// Value of the `id` attribute given to the note with the text of a spoiler.
+pub fn spoiler_note_anchor(number: usize) -> String {
+    format!("spoiler-{}", number)
+}
+
+/// Value of the `id` attribute given to the link to the note with the text of a spoiler.
+pub fn spoiler_ref_anchor(number: usize) -> String {
+    format!("spoiler-ref-{}", number)
+}
+
+/// Value of the `id` attribute given to a post.
+pub fn post_anchor(number: u64) -> String {
+    format!("p{}", number)
+}
+
+/// Children of a paragraph along with the tags of the formats that are currently open, with
+/// the innermost one last.
+#[derive(Default)]
+struct OpenTags {
+    children: Vec<Child>,
+    stack: Vec<(Format, Tag)>,
+}
+
+impl OpenTags {
+    fn push<C>(&mut self, child: C)
+    where
+        C: Into<Child>,
+    {
+        match self.stack.last_mut() {
+            Some((_, tag)) => {
+                tag.child(child);
             }
+            None => self.children.push(child.into()),
         };
-    };
+    }
+
+    fn open(&mut self, format: Format) {
+        self.stack.push((format, Tag::new(format.tag_name())));
+    }
+
+    fn pop(&mut self) -> Option<(Format, Tag)> {
+        self.stack.pop()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.stack.is_empty()
+    }
 }
 
-#[derive(Default)]
-pub struct LineParser {
-    open_spoiler: bool,
+pub struct LineParser<'a> {
+    spoilers: Vec<SpoilerSyntax>,
+    /// Formats that are currently open along with where they were opened, with the innermost one
+    /// last.
+    open: Vec<(Format, Position)>,
+    /// Number of lines parsed so far.
+    line: usize,
+    /// Tells if the spoiler that is currently open doesn't have any text yet.
+    is_spoiler_empty: bool,
+    diagnostics: Vec<Diagnostic>,
+    highlights: Vec<Highlight>,
+    posts: Option<(&'a PostIndex, &'a str)>,
+    anchored: HashSet<u64>,
+    spoiler_mode: SpoilerMode,
+    /// Notes with the text of spoilers, the last one is still being filled while a spoiler is
+    /// open.
+    notes: Vec<Tag>,
+    /// Paragraph of the current line for the note being filled.
+    note_line: Tag,
+    /// CSS class of the current line, given to its paragraph in the note being filled.
+    note_class: Option<String>,
 }
 
-impl LineParser {
+impl<'a> Default for LineParser<'a> {
+    fn default() -> Self {
+        Self {
+            spoilers: vec![SpoilerSyntax::default()],
+            open: Vec::new(),
+            line: 0,
+            is_spoiler_empty: false,
+            diagnostics: Vec::new(),
+            highlights: Vec::new(),
+            posts: None,
+            anchored: HashSet::new(),
+            spoiler_mode: SpoilerMode::default(),
+            notes: Vec::new(),
+            note_line: Tag::new("p"),
+            note_class: None,
+        }
+    }
+}
+
+impl<'a> LineParser<'a> {
+    /// Creates a parser that links post references to the posts in `index`, where `file` is the
+    /// chapter file that is being parsed.
+    pub fn with_posts(index: &'a PostIndex, file: &'a str) -> Self {
+        Self {
+            posts: Some((index, file)),
+            ..Default::default()
+        }
+    }
+
+    /// Sets the syntaxes recognized as spoilers, replacing the default `[spoiler]` one.
+    pub fn spoilers(&mut self, spoilers: Vec<SpoilerSyntax>) -> &mut Self {
+        self.spoilers = spoilers;
+        self
+    }
+
+    pub fn spoiler_mode(&mut self, mode: SpoilerMode) -> &mut Self {
+        self.spoiler_mode = mode;
+        self
+    }
+
+    /// Takes the notes with the text of the spoilers parsed so far, which are `aside` tags when
+    /// rendered as footnotes and `li` tags when rendered as endnotes.
+    pub fn take_notes(&mut self) -> Vec<Tag> {
+        self.notes.drain(0..).collect()
+    }
+
+    /// Sets the highlights to apply besides greentext.
+    pub fn highlights(&mut self, highlights: Vec<Highlight>) -> &mut Self {
+        self.highlights = highlights;
+        self
+    }
+
     pub fn is_spoiler_open(&self) -> bool {
-        self.open_spoiler
+        self.open
+            .iter()
+            .any(|(format, _)| matches!(format, Format::Spoiler(_)))
+    }
+
+    /// Tells if there is formatting other than a spoiler that is open.
+    pub fn is_formatting_open(&self) -> bool {
+        self.open
+            .iter()
+            .any(|(format, _)| !matches!(format, Format::Spoiler(_)))
+    }
+
+    /// Skips a line without parsing it, so positions of the following lines are kept right.
+    pub fn skip_line(&mut self) {
+        self.line += 1;
+    }
+
+    /// Records that the post is already anchored by its block, so its number isn't anchored
+    /// again when parsed.
+    pub fn anchor_post(&mut self, number: u64) {
+        self.anchored.insert(number);
+    }
+
+    /// Takes the problems found on the lines parsed so far.
+    pub fn take_diagnostics(&mut self) -> Vec<Diagnostic> {
+        self.diagnostics.drain(0..).collect()
+    }
+
+    /// Problems for the spoilers and formatting that are still open, found where they were
+    /// opened.
+    pub fn unclosed(&self) -> Vec<Diagnostic> {
+        self.open
+            .iter()
+            .map(|(format, position)| match format {
+                Format::Spoiler(_) => Diagnostic::new(*position, Problem::UnclosedSpoiler),
+                _ => Diagnostic::new(*position, Problem::UnclosedFormatting),
+            })
+            .collect()
+    }
+
+    fn delimiters(&self, format: Format) -> (&str, &str) {
+        match format {
+            Format::Spoiler(idx) => (&self.spoilers[idx].open, &self.spoilers[idx].close),
+            format => BBCODE_TAGS
+                .iter()
+                .find(|(bbcode, _, _)| *bbcode == format)
+                .map(|(_, open, close)| (*open, *close))
+                .expect("all formats besides spoilers are BBCode tags"),
+        }
+    }
+
+    /// CSS class of a paragraph that starts with the given text, greentext doesn't need any.
+    fn line_class(&self, text: &str) -> Option<&str> {
+        if text.starts_with(GREENTEXT_PREFIX) {
+            return None;
+        }
+
+        Some(
+            self.highlights
+                .iter()
+                .find(|highlight| text.starts_with(&highlight.prefix))
+                .map(|highlight| highlight.class.as_str())
+                .unwrap_or(RESET_FOREGROUND_CLASS),
+        )
+    }
+
+    fn post_link(&self, number: u64) -> Child {
+        let text = format!("{}{}", POST_REF_PREFIX, number);
+
+        match self.posts.and_then(|(index, _)| index.href(number)) {
+            Some(href) => Tag::new("a")
+                .attribute("class", QUOTELINK_CLASS)
+                .attribute("href", href)
+                .child(text)
+                .into(),
+            None => text.into(),
+        }
+    }
+
+    /// Tells if the post is found in the chapter being parsed and has yet to be given an anchor.
+    fn take_anchor(&mut self, number: u64) -> bool {
+        match self.posts {
+            Some((index, file)) if index.file(number) == Some(file) => self.anchored.insert(number),
+            _ => false,
+        }
+    }
+
+    /// Pushes text of the line, which is part of the spoiler if there is one open.
+    fn push_text<C>(&mut self, tags: &mut OpenTags, child: C)
+    where
+        C: Into<Child>,
+    {
+        self.is_spoiler_empty = false;
+        tags.push(child);
+    }
+
+    fn open_format(&mut self, tags: &mut OpenTags, format: Format, position: Position) {
+        let is_open = match format {
+            // Spoilers don't nest, no matter their syntax.
+            Format::Spoiler(_) => self.is_spoiler_open(),
+            format => self.open.iter().any(|(open, _)| *open == format),
+        };
+
+        match format {
+            // Symmetric delimiters only close a spoiler of their own syntax.
+            Format::Spoiler(idx)
+                if self.spoilers[idx].is_symmetric()
+                    && self.open.iter().any(|(open, _)| *open == format) =>
+            {
+                return self.close_format(tags, format, position);
+            }
+            // Place the delimiter as is if the format is already open.
+            _ if is_open => {
+                let delimiter = self.delimiters(format).0.to_string();
+                return self.push_text(tags, delimiter);
+            }
+            Format::Spoiler(_) => {
+                self.is_spoiler_empty = true;
+                self.open_spoiler_note(tags);
+            }
+            _ => {}
+        };
+
+        self.open.push((format, position));
+        tags.open(format);
+
+        if let (Format::Spoiler(_), SpoilerMode::Inline) = (format, self.spoiler_mode) {
+            tags.push("[");
+        }
+    }
+
+    /// Leaves a link to a new note for the text of the spoiler that is being opened.
+    fn open_spoiler_note(&mut self, tags: &mut OpenTags) {
+        let number = self.notes.len() + 1;
+        let mut link = Tag::new("a");
+        link.attribute("id", spoiler_ref_anchor(number))
+            .attribute("href", format!("#{}", spoiler_note_anchor(number)))
+            .child(format!("[{}]", number));
+
+        let mut note = match self.spoiler_mode {
+            SpoilerMode::Footnote => {
+                link.attribute("epub:type", "noteref");
+                let mut note = Tag::new("aside");
+                note.attribute("epub:type", "footnote");
+                note
+            }
+            SpoilerMode::Endnote => {
+                let mut note = Tag::new("li");
+                note.attribute("epub:type", "endnote");
+                note
+            }
+            _ => return,
+        };
+        note.attribute("id", spoiler_note_anchor(number));
+
+        tags.push(link);
+        self.notes.push(note);
+    }
+
+    /// Moves the paragraph of the current line to the note being filled.
+    fn flush_note_line(&mut self) {
+        let mut line = std::mem::replace(&mut self.note_line, Tag::new("p"));
+        if line.is_empty() {
+            return;
+        }
+
+        if let Some(class) = &self.note_class {
+            line.attribute("class", class);
+        }
+
+        if let Some(note) = self.notes.last_mut() {
+            note.child(line);
+        }
+    }
+
+    /// Closes the innermost tag, which is dropped if it ended up empty.
+    fn close_tag(&mut self, tags: &mut OpenTags) {
+        match tags.pop() {
+            Some((Format::Spoiler(_), tag)) if self.spoiler_mode.is_note() => {
+                for child in tag.into_children() {
+                    self.note_line.child(child);
+                }
+            }
+            Some((_, tag)) if !tag.is_empty() => tags.push(tag),
+            _ => {}
+        };
+    }
+
+    fn close_format(&mut self, tags: &mut OpenTags, format: Format, position: Position) {
+        let idx = match self.open.iter().position(|(open, _)| *open == format) {
+            Some(idx) => idx,
+            // Place the delimiter as is if there isn't an open format to close.
+            None => {
+                let problem = match format {
+                    Format::Spoiler(_) => Problem::StraySpoilerClose,
+                    _ => Problem::StrayFormattingClose,
+                };
+                self.diagnostics.push(Diagnostic::new(position, problem));
+
+                let delimiter = self.delimiters(format).1.to_string();
+                return self.push_text(tags, delimiter);
+            }
+        };
+
+        // Formats opened after the one being closed are closed along with it and then reopened,
+        // so tags are always properly nested.
+        let reopened = self.open.split_off(idx + 1);
+        for _ in 0..reopened.len() {
+            self.close_tag(tags);
+        }
+
+        if let (Format::Spoiler(_), SpoilerMode::Inline) = (format, self.spoiler_mode) {
+            tags.push("]");
+        }
+
+        self.close_tag(tags);
+        if let Some((Format::Spoiler(_), opened_at)) = self.open.pop() {
+            if self.is_spoiler_empty {
+                self.diagnostics
+                    .push(Diagnostic::new(opened_at, Problem::EmptySpoiler));
+            }
+        }
+
+        if let Format::Spoiler(_) = format {
+            if self.spoiler_mode == SpoilerMode::Endnote {
+                let number = self.notes.len();
+                self.note_line.child(" ").child(
+                    Tag::new("a")
+                        .attribute("href", format!("#{}", spoiler_ref_anchor(number)))
+                        .child("\u{21a9}"),
+                );
+            }
+
+            self.flush_note_line();
+        }
+
+        for (format, opened_at) in reopened {
+            self.open.push((format, opened_at));
+            tags.open(format);
+        }
     }
 
-    pub fn parse<S: ?Sized>(&mut self, line: &S) -> Tag
+    pub fn parse<S>(&mut self, line: &S) -> Tag
     where
-        S: AsRef<str>,
+        S: AsRef<str> + ?Sized,
     {
+        self.line += 1;
+        let tokens = tokenize(self.line, line, &self.spoilers);
+        self.parse_tokens(tokens)
+    }
+
+    /// Same as [`parse`](Self::parse) for a line with its markup already known.
+    pub fn parse_marked(&mut self, line: &MarkedLine) -> Tag {
+        self.line += 1;
+        let tokens = line.positioned_tokens(self.line);
+        self.parse_tokens(tokens)
+    }
+
+    fn parse_tokens(&mut self, tokens: Vec<(Position, Token)>) -> Tag {
         let mut paragraph = Tag::new("p");
         let mut is_first_text = true;
 
-        let mut children: Vec<Child> = Vec::new();
+        let mut tags = OpenTags::default();
+        for (format, _) in &self.open {
+            tags.open(*format);
+        }
+        self.note_class = Some(RESET_FOREGROUND_CLASS.into());
 
-        for token in tokenize(line) {
-            // Remove highlight if it doesn't apply to the given line
+        for (position, token) in tokens {
+            // Change or remove highlight if greentext doesn't apply to the given line
             if is_first_text {
-                if let Token::Text(text) = &token {
-                    if !text.starts_with('>') {
-                        paragraph.attribute("class", RESET_FOREGROUND_CLASS);
+                let class = match &token {
+                    Token::Text(text) => Some(self.line_class(text).map(String::from)),
+                    Token::PostRef(_) | Token::PostNumber(_) => {
+                        Some(Some(RESET_FOREGROUND_CLASS.into()))
+                    }
+                    _ => None,
+                };
+
+                if let Some(class) = class {
+                    if let Some(class) = &class {
+                        paragraph.attribute("class", class);
                     }
+                    self.note_class = class;
                     is_first_text = false;
                 }
             }
 
             match token {
-                Token::Text(text) if self.open_spoiler => {
-                    push_span_tag!(children, text);
+                Token::Text(text) => self.push_text(&mut tags, text),
+                Token::PostRef(number) => {
+                    let link = self.post_link(number);
+                    self.push_text(&mut tags, link);
                 }
-                Token::Text(text) => {
-                    children.push(text.into());
-                }
-                // Place the tag as is if `open_spoiler` would stay the same.
-                Token::SpoilerOpen if self.open_spoiler => {
-                    push_span_tag!(children, SPOILER_OPEN_TAG);
-                }
-                Token::SpoilerClose if !self.open_spoiler => {
-                    push_span_tag!(children, SPOILER_CLOSE_TAG);
+                Token::PostNumber(number) => {
+                    if self.take_anchor(number) {
+                        paragraph.attribute("id", post_anchor(number));
+                    }
+
+                    self.push_text(&mut tags, format!("{}{}", POST_NUMBER_PREFIX, number));
                 }
-                Token::SpoilerOpen => self.open_spoiler = true,
-                Token::SpoilerClose => self.open_spoiler = false,
+                Token::Open(format) => self.open_format(&mut tags, format, position),
+                Token::Close(format) => self.close_format(&mut tags, format, position),
             };
         }
 
-        for child in children.drain(0..) {
+        while !tags.is_empty() {
+            self.close_tag(&mut tags);
+        }
+        self.flush_note_line();
+
+        for child in tags.children {
             paragraph.child(child);
         }
 
@@ -191,16 +921,18 @@ mod test {
         (spoiler, $parser:ident, $expected:expr, $raw:expr) => {
             assert_eq!(&$parser.parse($raw), $expected);
             assert_eq!(
-                $parser.open_spoiler, true,
-                "expected `open_spoiler` attribute to be `true`: {:?}",
+                $parser.is_spoiler_open(),
+                true,
+                "expected `is_spoiler_open()` to be true: {:?}",
                 $raw
             );
         };
         ($parser:ident, $expected:expr, $raw:expr) => {
             assert_eq!(&$parser.parse($raw), $expected);
             assert_eq!(
-                $parser.open_spoiler, false,
-                "expected `open_spoiler` attribute to be `false`: {:?}",
+                $parser.is_spoiler_open(),
+                false,
+                "expected `is_spoiler_open()` to be false: {:?}",
                 $raw
             );
         };
@@ -477,4 +1209,400 @@ mod test {
             "[/spoiler]Nothing here either"
         );
     }
+
+    #[test]
+    fn post_references() {
+        let mut posts = PostIndex::default();
+        posts.insert(123456, "paste-002.xhtml");
+        let mut parser = LineParser::with_posts(&posts, "paste-001.xhtml");
+
+        let link = Tag::new("a")
+            .attribute("class", QUOTELINK_CLASS)
+            .attribute("href", "paste-002.xhtml#p123456")
+            .child(">>123456")
+            .clone();
+
+        assert_parse!(parser, tag!(link.clone()), ">>123456");
+        assert_parse!(parser, tag!(link.clone(), " is right"), ">>123456 is right");
+        assert_parse!(parser, tag!(hi, ">be ", link.clone()), ">be >>123456");
+        assert_parse!(
+            parser,
+            tag!(spoiler!("it was ", link)),
+            "[spoiler]it was >>123456[/spoiler]"
+        );
+        // Not found in any chapter.
+        assert_parse!(parser, tag!(">>654321"), ">>654321");
+        assert_parse!(parser, tag!(hi, ">>not a reference"), ">>not a reference");
+    }
+
+    #[test]
+    fn post_anchors() {
+        let mut posts = PostIndex::default();
+        posts.insert(123456, "paste-001.xhtml");
+        let mut parser = LineParser::with_posts(&posts, "paste-001.xhtml");
+
+        assert_parse!(
+            parser,
+            Tag::new("p")
+                .attribute("class", RESET_FOREGROUND_CLASS)
+                .attribute("id", "p123456")
+                .child("Anonymous ")
+                .child("No.123456"),
+            "Anonymous No.123456"
+        );
+        // Only its first appearance gets the anchor.
+        assert_parse!(
+            parser,
+            tag!("Anonymous ", "No.123456"),
+            "Anonymous No.123456"
+        );
+        assert_parse!(
+            parser,
+            tag!("Anonymous ", "No.654321"),
+            "Anonymous No.654321"
+        );
+        assert_parse!(parser, tag!("YesNo.123456"), "YesNo.123456");
+        // Numbers in the text of a post aren't taken for the number of the post.
+        assert_parse!(parser, tag!("No.123456 fan"), "No.123456 fan");
+        assert_parse!(
+            parser,
+            tag!("It was No.123456 on the list"),
+            "It was No.123456 on the list"
+        );
+        assert_parse!(
+            parser,
+            tag!("Anonymous ", "No.123456", " ▶ ", ">>123457"),
+            "Anonymous No.123456 ▶ >>123457"
+        );
+    }
+
+    #[test]
+    fn other_highlights() {
+        let mut parser = LineParser::default();
+        parser.highlights(vec![
+            Highlight::orangetext("#E07000"),
+            Highlight::caret("#6C8EBF"),
+        ]);
+
+        macro_rules! highlight {
+            ($class:expr, $($tag:expr),+) => {
+                Tag::new("p")
+                    .attribute("class", $class)
+                    $( .child($tag) )+
+            };
+        }
+
+        assert_parse!(parser, tag!(hi, ">Greentext"), ">Greentext");
+        assert_parse!(
+            parser,
+            highlight!("orangetext", "<Orangetext"),
+            "<Orangetext"
+        );
+        assert_parse!(parser, highlight!("caret", "^Caret"), "^Caret");
+        assert_parse!(
+            parser,
+            highlight!("orangetext", spoiler!("<Orangetext in a spoiler")),
+            "[spoiler]<Orangetext in a spoiler[/spoiler]"
+        );
+        assert_parse!(parser, tag!("Not <highlighted"), "Not <highlighted");
+
+        // Not highlighted without the rules.
+        let mut parser = LineParser::default();
+        assert_parse!(parser, tag!("<Orangetext"), "<Orangetext");
+    }
+
+    #[test]
+    fn other_spoiler_syntaxes() {
+        let mut parser = LineParser::default();
+        parser.spoilers(vec![
+            SpoilerSyntax::bbcode(),
+            SpoilerSyntax::discord(),
+            SpoilerSyntax::reddit(),
+        ]);
+
+        assert_parse!(
+            parser,
+            tag!("A ", spoiler!("Discord"), " spoiler"),
+            "A ||Discord|| spoiler"
+        );
+        assert_parse!(
+            parser,
+            tag!("A ", spoiler!("Reddit"), " spoiler"),
+            "A >!Reddit!< spoiler"
+        );
+        assert_parse!(
+            parser,
+            tag!(spoiler!("Reddit"), " at the start isn't greentext"),
+            ">!Reddit!< at the start isn't greentext"
+        );
+        assert_parse!(
+            parser,
+            tag!(spoiler!("Mixed"), " ", spoiler!("syntaxes")),
+            "||Mixed|| [spoiler]syntaxes[/spoiler]"
+        );
+
+        // Delimiters of other syntaxes are kept as is while a spoiler is open.
+        assert_parse!(
+            parser,
+            tag!(spoiler!("Only ", "[/spoiler]", " closes with its own")),
+            "||Only [/spoiler] closes with its own||"
+        );
+        assert_parse!(
+            parser,
+            tag!(spoiler!("Not nested ", ">!", " either")),
+            "[spoiler]Not nested >! either[/spoiler]"
+        );
+        assert_parse!(parser, tag!("Stray ", "!<"), "Stray !<");
+        parser.take_diagnostics();
+
+        // Symmetric delimiters of another syntax don't close the open spoiler either.
+        assert_parse!(
+            parser,
+            tag!(spoiler!("Literal ", "||", " pipes")),
+            "[spoiler]Literal || pipes[/spoiler]"
+        );
+        assert_eq!(parser.take_diagnostics(), []);
+    }
+
+    #[test]
+    fn multiple_line_symmetric_spoiler() {
+        let mut parser = LineParser::default();
+        parser.spoilers(vec![SpoilerSyntax::discord()]);
+
+        assert_parse!(
+            spoiler,
+            parser,
+            tag!("Starts normal ", spoiler!("and then an spoiler opens")),
+            "Starts normal ||and then an spoiler opens"
+        );
+        assert_parse!(
+            spoiler,
+            parser,
+            tag!(spoiler!("The unclosed spoiler continues on this line")),
+            "The unclosed spoiler continues on this line"
+        );
+        assert_parse!(
+            parser,
+            tag!(
+                spoiler!("The spoiler ends here"),
+                " and continues as normal"
+            ),
+            "The spoiler ends here|| and continues as normal"
+        );
+        // Not recognized anymore.
+        assert_parse!(
+            parser,
+            tag!("[spoiler]Not a spoiler[/spoiler]"),
+            "[spoiler]Not a spoiler[/spoiler]"
+        );
+    }
+
+    #[test]
+    fn bbcode_formatting() {
+        let mut parser = LineParser::default();
+
+        macro_rules! format {
+            ($name:expr, $($content:expr),+) => {
+                Tag::new($name)
+                    $( .child($content) )+
+            };
+        }
+
+        assert_parse!(
+            parser,
+            tag!(
+                format!("strong", "Bold"),
+                " ",
+                format!("em", "italic"),
+                " ",
+                format!("u", "underline"),
+                " ",
+                format!("s", "strikethrough"),
+                " ",
+                format!("code", "code")
+            ),
+            "[b]Bold[/b] [i]italic[/i] [u]underline[/u] [s]strikethrough[/s] [code]code[/code]"
+        );
+        assert_parse!(
+            parser,
+            tag!(hi, format!("strong", ">Bold greentext")),
+            "[b]>Bold greentext[/b]"
+        );
+        assert_parse!(
+            parser,
+            tag!(format!("strong", "Bold ", format!("em", "and italic"))),
+            "[b]Bold [i]and italic[/i][/b]"
+        );
+        assert_parse!(
+            parser,
+            tag!(
+                spoiler!("Spoiler ", format!("strong", "with bold")),
+                format!("strong", " outside")
+            ),
+            "[spoiler]Spoiler [b]with bold[/spoiler] outside[/b]"
+        );
+        assert_parse!(
+            parser,
+            tag!(format!("strong", "Not ", "[b]", "nested")),
+            "[b]Not [b]nested[/b]"
+        );
+        assert_parse!(parser, tag!("Stray ", "[/i]"), "Stray [/i]");
+        assert_parse!(parser, tag!("Empty"), "[b][/b]Empty");
+    }
+
+    #[test]
+    fn multiple_line_formatting() {
+        let mut parser = LineParser::default();
+
+        parser.parse("[b]Bold [spoiler]and a spoiler");
+        assert!(parser.is_formatting_open());
+        assert_parse!(
+            spoiler,
+            parser,
+            tag!(Tag::new("strong").child(spoiler!("The spoiler continues"))),
+            "The spoiler continues"
+        );
+        assert_parse!(
+            parser,
+            tag!(Tag::new("strong")
+                .child(spoiler!("Until here"))
+                .child(" and bold until here")),
+            "Until here[/spoiler] and bold until here[/b]"
+        );
+        assert!(!parser.is_formatting_open());
+    }
+
+    #[test]
+    fn short_spoiler_over_strikethrough() {
+        let mut parser = LineParser::default();
+        parser.spoilers(vec![SpoilerSyntax::bbcode_short()]);
+
+        assert_parse!(parser, tag!("A ", spoiler!("spoiler")), "A [s]spoiler[/s]");
+    }
+
+    #[test]
+    fn inline_spoilers() {
+        let mut parser = LineParser::default();
+        parser.spoiler_mode(SpoilerMode::Inline);
+
+        assert_parse!(
+            parser,
+            tag!("A ", spoiler!("[", "spoiler", "]"), " in brackets"),
+            "A [spoiler]spoiler[/spoiler] in brackets"
+        );
+        assert_parse!(
+            spoiler,
+            parser,
+            tag!(spoiler!("[", "Brackets on")),
+            "[spoiler]Brackets on"
+        );
+        assert_parse!(
+            parser,
+            tag!(spoiler!("both ends", "]")),
+            "both ends[/spoiler]"
+        );
+    }
+
+    #[test]
+    fn footnote_spoilers() {
+        let mut parser = LineParser::default();
+        parser.spoiler_mode(SpoilerMode::Footnote);
+
+        let noteref = |number: usize| {
+            Tag::new("a")
+                .attribute("id", spoiler_ref_anchor(number))
+                .attribute("href", format!("#{}", spoiler_note_anchor(number)))
+                .child(format!("[{}]", number))
+                .attribute("epub:type", "noteref")
+                .clone()
+        };
+
+        assert_parse!(
+            parser,
+            tag!("A ", noteref(1), " spoiler"),
+            "A [spoiler]hidden[/spoiler] spoiler"
+        );
+        assert_parse!(spoiler, parser, tag!(noteref(2)), "[spoiler]Spoiler on");
+        parser.parse(">two lines[/spoiler]");
+
+        assert_eq!(
+            parser.take_notes(),
+            vec![
+                Tag::new("aside")
+                    .attribute("epub:type", "footnote")
+                    .attribute("id", "spoiler-1")
+                    .child(tag!("hidden"))
+                    .clone(),
+                Tag::new("aside")
+                    .attribute("epub:type", "footnote")
+                    .attribute("id", "spoiler-2")
+                    .child(tag!("Spoiler on"))
+                    .child(tag!(hi, ">two lines"))
+                    .clone(),
+            ]
+        );
+        assert!(parser.take_notes().is_empty());
+    }
+
+    #[test]
+    fn endnote_spoilers() {
+        let mut parser = LineParser::default();
+        parser.spoiler_mode(SpoilerMode::Endnote);
+
+        assert_parse!(
+            parser,
+            tag!(Tag::new("a")
+                .attribute("id", "spoiler-ref-1")
+                .attribute("href", "#spoiler-1")
+                .child("[1]")),
+            "[spoiler]hidden[/spoiler]"
+        );
+
+        assert_eq!(
+            parser.take_notes(),
+            vec![Tag::new("li")
+                .attribute("epub:type", "endnote")
+                .attribute("id", "spoiler-1")
+                .child(tag!(
+                    "hidden",
+                    " ",
+                    Tag::new("a")
+                        .attribute("href", "#spoiler-ref-1")
+                        .child("\u{21a9}")
+                ))
+                .clone()]
+        );
+    }
+
+    #[test]
+    fn token_positions() {
+        let positions: Vec<Position> =
+            tokenize(3, "é [spoiler]>>1 x[/spoiler]", &[SpoilerSyntax::bbcode()])
+                .into_iter()
+                .map(|(position, _)| position)
+                .collect();
+        assert_eq!(
+            positions,
+            [(3, 1), (3, 3), (3, 12), (3, 15), (3, 17)]
+                .iter()
+                .map(|(line, column)| Position::new(*line, *column))
+                .collect::<Vec<Position>>()
+        );
+    }
+
+    #[test]
+    fn unclosed_positions() {
+        let mut parser = LineParser::default();
+        parser.parse("Line");
+        parser.skip_line();
+        parser.parse(">be [spoiler]me [b]and");
+
+        assert_eq!(
+            parser.unclosed(),
+            vec![
+                Diagnostic::new(Position::new(3, 5), Problem::UnclosedSpoiler),
+                Diagnostic::new(Position::new(3, 17), Problem::UnclosedFormatting),
+            ]
+        );
+    }
 }
@@ -0,0 +1,324 @@
+//! Stylesheets added after the generated one, checked to be well formed and with the files they
+//! refer to packed in the book.
+
+use std::ops::Range;
+
+use crate::errors::{CliError, CliResult};
+
+/// Directory of the book where files referred to by stylesheets are stored, relative to the
+/// stylesheet.
+pub const RESOURCES_DIR: &str = "res";
+
+/// Media type of a file referred to by a stylesheet, from its extension.
+pub fn resource_mime_type(path: &str) -> Option<&'static str> {
+    let extension = path.rsplit_once('.')?.1.to_ascii_lowercase();
+    let mime_type = match extension.as_str() {
+        "woff2" => "font/woff2",
+        "woff" => "font/woff",
+        "ttf" => "font/ttf",
+        "otf" => "font/otf",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        _ => return None,
+    };
+    Some(mime_type)
+}
+
+/// `url()` found in a stylesheet.
+#[derive(Debug, Clone, PartialEq)]
+struct Url {
+    /// Where the whole `url()` is in the stylesheet.
+    range: Range<usize>,
+    /// Path of the file, without any query or fragment.
+    path: String,
+}
+
+impl Url {
+    /// Tells if it refers to a file next to the stylesheet instead of a remote one, data or an
+    /// element of the document.
+    fn is_local(&self) -> bool {
+        !(self.path.is_empty()
+            || self.path.starts_with('/')
+            || self
+                .path
+                .split_once(':')
+                .is_some_and(|(scheme, _)| !scheme.contains('/')))
+    }
+}
+
+/// Checks a stylesheet is well formed, which is that all its strings and comments are closed
+/// and its brackets are balanced, and finds the `url()`s in it.
+fn scan(css: &str) -> CliResult<Vec<Url>> {
+    let bytes = css.as_bytes();
+    let line = |index: usize| css[..index].matches('\n').count() + 1;
+    fn error<T>(message: String) -> CliResult<T> {
+        Err(CliError::from(format!("invalid stylesheet: {}", message)))
+    }
+
+    // End of the string starting with a quote at the given index, after its closing quote.
+    let string_end = |start: usize| -> CliResult<usize> {
+        let quote = bytes[start];
+        let mut index = start + 1;
+        while index < bytes.len() {
+            match bytes[index] {
+                b'\\' => index += 2,
+                b'\n' => break,
+                byte if byte == quote => return Ok(index + 1),
+                _ => index += 1,
+            }
+        }
+        error(format!("string at line {} is never closed", line(start)))
+    };
+
+    let mut urls = Vec::new();
+    let mut brackets: Vec<(u8, usize)> = Vec::new();
+    let mut index = 0;
+    while index < bytes.len() {
+        let rest = &css[index..];
+        match bytes[index] {
+            b'/' if rest.starts_with("/*") => match rest[2..].find("*/") {
+                Some(end) => index += end + 4,
+                None => return error(format!("comment at line {} is never closed", line(index))),
+            },
+            b'"' | b'\'' => index = string_end(index)?,
+            b'{' | b'(' | b'[' => {
+                brackets.push((bytes[index], index));
+                index += 1;
+            }
+            b'}' | b')' | b']' => {
+                let opening = match bytes[index] {
+                    b'}' => b'{',
+                    b')' => b'(',
+                    _ => b'[',
+                };
+                match brackets.pop() {
+                    Some((bracket, _)) if bracket == opening => index += 1,
+                    _ => {
+                        return error(format!(
+                            "unexpected `{}` at line {}",
+                            bytes[index] as char,
+                            line(index)
+                        ))
+                    }
+                }
+            }
+            b'@' if rest.len() >= 7 && rest[..7].eq_ignore_ascii_case("@import") => {
+                return error(format!(
+                    "`@import` at line {} isn't supported, stylesheets have to be given one by one",
+                    line(index)
+                ));
+            }
+            b'u' | b'U'
+                if rest.len() >= 4
+                    && rest[..4].eq_ignore_ascii_case("url(")
+                    && !css[..index].ends_with(|c: char| c.is_alphanumeric() || c == '-') =>
+            {
+                let start = index;
+                let mut value_start = index + 4;
+                while bytes.get(value_start).is_some_and(u8::is_ascii_whitespace) {
+                    value_start += 1;
+                }
+
+                let (value, mut end) = match bytes.get(value_start) {
+                    Some(b'"' | b'\'') => {
+                        let end = string_end(value_start)?;
+                        (&css[value_start + 1..end - 1], end)
+                    }
+                    _ => {
+                        let end = css[value_start..]
+                            .find(|c: char| c == ')' || c.is_whitespace() || "\"'(".contains(c))
+                            .map_or(css.len(), |end| value_start + end);
+                        (&css[value_start..end], end)
+                    }
+                };
+                while bytes.get(end).is_some_and(u8::is_ascii_whitespace) {
+                    end += 1;
+                }
+                if bytes.get(end) != Some(&b')') {
+                    return error(format!("`url(` at line {} is never closed", line(start)));
+                }
+
+                let path = value.split(['?', '#']).next().unwrap_or_default();
+                urls.push(Url {
+                    range: start..end + 1,
+                    path: path.to_string(),
+                });
+                index = end + 1;
+            }
+            _ => index += 1,
+        }
+    }
+
+    match brackets.last() {
+        Some((bracket, index)) => error(format!(
+            "`{}` at line {} is never closed",
+            *bracket as char,
+            line(*index)
+        )),
+        None => Ok(urls),
+    }
+}
+
+/// File referred to by a stylesheet, with the path it gets in the book.
+pub(crate) type PackedFile<'a> = (String, &'a [u8]);
+
+/// Stylesheet added after the generated one, along with the files it refers to.
+#[derive(Debug, Clone)]
+pub struct UserStylesheet {
+    css: String,
+    urls: Vec<Url>,
+    /// Paths as written in the stylesheet along with the content of the files.
+    resources: Vec<(String, Vec<u8>)>,
+}
+
+impl UserStylesheet {
+    /// Fails if the stylesheet isn't well formed.
+    pub fn new(css: String) -> CliResult<Self> {
+        let urls = scan(&css)?;
+        Ok(Self {
+            css,
+            urls,
+            resources: Vec::new(),
+        })
+    }
+
+    /// Paths of the files the stylesheet refers to, relative to it, which have to be packed in
+    /// the book. Remote files and data URLs are left as they are.
+    pub fn files(&self) -> Vec<&str> {
+        let mut files: Vec<&str> = Vec::new();
+        for url in self.urls.iter().filter(|url| url.is_local()) {
+            if !files.contains(&url.path.as_str()) {
+                files.push(&url.path);
+            }
+        }
+        files
+    }
+
+    /// Packs a file the stylesheet refers to, which has to be a font or an image.
+    pub fn resource<S>(&mut self, path: S, bytes: Vec<u8>) -> CliResult<&mut Self>
+    where
+        S: ToString,
+    {
+        let path = path.to_string();
+        if resource_mime_type(&path).is_none() {
+            return Err(CliError::from(format!(
+                "unsupported file referred to by a stylesheet: {:?}, it has to be a font or an image",
+                path
+            )));
+        }
+
+        self.resources.push((path, bytes));
+        Ok(self)
+    }
+
+    /// The stylesheet with its files, with each file given the path it gets in the book, from
+    /// the ones returned by `href` for the file name of each of them.
+    pub(crate) fn pack<F>(&self, mut href: F) -> CliResult<(String, Vec<PackedFile<'_>>)>
+    where
+        F: FnMut(&str) -> String,
+    {
+        let mut packed: Vec<(&str, String, &[u8])> = Vec::new();
+        for (path, bytes) in &self.resources {
+            let name = path.rsplit('/').next().unwrap_or(path);
+            packed.push((path, href(name), bytes));
+        }
+
+        let mut css = String::with_capacity(self.css.len());
+        let mut last = 0;
+        for url in self.urls.iter().filter(|url| url.is_local()) {
+            let href = match packed.iter().find(|(path, _, _)| *path == url.path) {
+                Some((_, href, _)) => href,
+                None => {
+                    return Err(CliError::from(format!(
+                        "file referred to by a stylesheet isn't packed: {:?}",
+                        url.path
+                    )))
+                }
+            };
+
+            css.push_str(&self.css[last..url.range.start]);
+            css.push_str(&format!("url(\"{}\")", href));
+            last = url.range.end;
+        }
+        css.push_str(&self.css[last..]);
+
+        Ok((
+            css,
+            packed
+                .into_iter()
+                .map(|(_, href, bytes)| (href, bytes))
+                .collect(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn well_formed_stylesheets() {
+        let urls = scan(
+            "/* a } comment */\n\
+            @font-face { font-family: \"A\"; src: url( 'fonts/a.woff2?v=1' ) format(\"woff2\"); }\n\
+            body { background: url(img/bg.png), url(data:image/png;base64,AAAA); }\n\
+            p::before { content: \"{\\\"\"; }",
+        )
+        .unwrap();
+        assert_eq!(
+            urls.iter()
+                .map(|url| url.path.as_str())
+                .collect::<Vec<&str>>(),
+            ["fonts/a.woff2", "img/bg.png", "data:image/png;base64,AAAA"]
+        );
+        assert!(urls[0].is_local() && urls[1].is_local() && !urls[2].is_local());
+
+        for css in [
+            "p { color: red;",
+            "p { color: red; }}",
+            "p { content: \"a; }",
+            "/* p { }",
+            "p { background: url(a.png; }",
+            "@import \"other.css\";",
+            "p { color: rgb(0, 0, 0]; }",
+        ] {
+            assert!(scan(css).is_err(), "{:?} is invalid", css);
+        }
+    }
+
+    #[test]
+    fn pack_resources() {
+        let mut stylesheet = UserStylesheet::new(String::from(
+            "@font-face { src: url(\"fonts/a.woff2\"); }\n\
+            body { background: url(img/bg.png) url(https://example.com/a.png); }",
+        ))
+        .unwrap();
+        assert_eq!(stylesheet.files(), ["fonts/a.woff2", "img/bg.png"]);
+        assert!(stylesheet.resource("a.css", Vec::new()).is_err());
+        assert!(stylesheet.pack(|name| name.to_string()).is_err());
+
+        stylesheet
+            .resource("fonts/a.woff2", b"font".to_vec())
+            .unwrap()
+            .resource("img/bg.png", b"image".to_vec())
+            .unwrap();
+        let (css, resources) = stylesheet
+            .pack(|name| format!("{}/{}", RESOURCES_DIR, name))
+            .unwrap();
+        assert_eq!(
+            css,
+            "@font-face { src: url(\"res/a.woff2\"); }\n\
+            body { background: url(\"res/bg.png\") url(https://example.com/a.png); }"
+        );
+        assert_eq!(
+            resources,
+            [
+                (String::from("res/a.woff2"), b"font".as_slice()),
+                (String::from("res/bg.png"), b"image".as_slice())
+            ]
+        );
+    }
+}
@@ -82,6 +82,15 @@ impl Tag {
         self.children.push(child.into());
         self
     }
+
+    pub fn into_children(self) -> Vec<Child> {
+        self.children
+    }
+
+    /// Tells if the tag doesn't have any children.
+    pub fn is_empty(&self) -> bool {
+        self.children.is_empty()
+    }
 }
 
 impl fmt::Display for Tag {